@@ -10,17 +10,53 @@ use crate::interpreter::Environment;
 pub use crate::interpreter::Interpreter;
 pub use crate::object::Object;
 
-#[derive(Debug, Clone)]
+// Host code behind a native function. It gets the interpreter so it can call
+// back into Lox, and can fail with a `RuntimeError` like any Lox code.
+pub type NativeFn = Rc<dyn Fn(&mut Interpreter, Vec<Object>) -> Result<Object, RuntimeError>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    Fixed(usize),
+    // Inclusive on both ends.
+    Range(usize, usize),
+    // Any number of arguments, at least this many.
+    Variadic(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Fixed(arity) => count == arity,
+            Arity::Range(min, max) => min <= count && count <= max,
+            Arity::Variadic(min) => min <= count,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arity::Fixed(arity) => write!(f, "{}", arity),
+            Arity::Range(min, max) => write!(f, "{} to {}", min, max),
+            Arity::Variadic(min) => write!(f, "at least {}", min),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub enum LoxFunc {
-    Callable {
-        arity: usize,
-        func: Box<fn(Vec<Object>) -> Object>,
+    Native {
+        name: String,
+        arity: Arity,
+        func: NativeFn,
     },
+    // Shared, so binding a method to an instance doesn't copy its body.
     Function {
         name: Token,
-        params: Vec<Token>,
-        body: Vec<Stmt>,
+        params: Rc<[Token]>,
+        body: Rc<[Stmt]>,
         closure: Rc<RefCell<Environment>>,
+        is_initializer: bool,
     },
 }
 
@@ -31,11 +67,12 @@ impl LoxFunc {
         arguments: Vec<Object>,
     ) -> Result<Object, RuntimeError> {
         match self {
-            LoxFunc::Callable { func, .. } => Ok(func(arguments)),
+            LoxFunc::Native { func, .. } => func(interpreter, arguments),
             LoxFunc::Function {
                 params,
                 body,
                 closure,
+                is_initializer,
                 ..
             } => {
                 let environment = Rc::new(RefCell::new(Environment::new_with_enclosing(closure)));
@@ -45,28 +82,98 @@ impl LoxFunc {
                         .define(param.lexeme.clone(), arg.clone());
                 }
 
-                match interpreter.execute_block(body.clone(), environment) {
-                    Ok(()) => Ok(Object::Nil),
-                    Err(Error::ReturnError { value }) => Ok(value),
-                    _ => Ok(Object::Nil),
+                let value = match interpreter.execute_block(body.to_vec(), environment) {
+                    Ok(()) => Object::Nil,
+                    Err(Error::ReturnError { value, .. }) => value,
+                    Err(error) => return Err(error.into_runtime_error()),
+                };
+                // An initializer always hands back the instance, even from
+                // an early `return;`.
+                if *is_initializer {
+                    return Ok(closure.borrow().get_local("this").unwrap_or_default());
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    // Returns a copy of this method whose closure has `this` bound to
+    // `instance`.
+    pub fn bind(&self, instance: Object) -> LoxFunc {
+        match self {
+            LoxFunc::Function {
+                name,
+                params,
+                body,
+                closure,
+                is_initializer,
+            } => {
+                let mut environment = Environment::new_with_enclosing(closure);
+                environment.define("this".to_string(), instance);
+                LoxFunc::Function {
+                    name: name.clone(),
+                    params: Rc::clone(params),
+                    body: Rc::clone(body),
+                    closure: Rc::new(RefCell::new(environment)),
+                    is_initializer: *is_initializer,
                 }
             }
+            LoxFunc::Native { .. } => self.clone(),
         }
     }
 
-    pub fn arity(&self) -> usize {
+    pub fn native<F>(name: &str, arity: Arity, func: F) -> LoxFunc
+    where
+        F: Fn(&mut Interpreter, Vec<Object>) -> Result<Object, RuntimeError> + 'static,
+    {
+        LoxFunc::Native {
+            name: name.to_string(),
+            arity,
+            func: Rc::new(func),
+        }
+    }
+
+    pub fn name(&self) -> &str {
         match self {
-            LoxFunc::Callable { arity, .. } => *arity,
-            LoxFunc::Function { params, .. } => params.len(),
+            LoxFunc::Native { name, .. } => name,
+            LoxFunc::Function { name, .. } => &name.lexeme,
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self {
+            LoxFunc::Native { arity, .. } => *arity,
+            LoxFunc::Function { params, .. } => Arity::Fixed(params.len()),
+        }
+    }
+}
+
+// The closure can contain the function itself, so only the signature is
+// printed.
+impl fmt::Debug for LoxFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoxFunc::Native { name, arity, .. } => f
+                .debug_struct("Native")
+                .field("name", name)
+                .field("arity", arity)
+                .finish(),
+            LoxFunc::Function { name, params, .. } => {
+                let params: Vec<&String> = params.iter().map(|param| &param.lexeme).collect();
+                f.debug_struct("Function")
+                    .field("name", &name.lexeme)
+                    .field("params", &params)
+                    .finish()
+            }
         }
     }
 }
 
-impl<'a> fmt::Display for LoxFunc {
+impl fmt::Display for LoxFunc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            LoxFunc::Callable { .. } => write!(f, "Callable"),
-            LoxFunc::Function { name, .. } => write!(f, "<fn {}>", name),
+            LoxFunc::Native { .. } => write!(f, "<native fn>"),
+            LoxFunc::Function { name, .. } => write!(f, "<fn {}>", name.lexeme),
         }
     }
 }
@@ -0,0 +1,133 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::callable::{Arity, LoxFunc};
+use crate::error::RuntimeError;
+use crate::token::Token;
+
+pub use crate::interpreter::Interpreter;
+pub use crate::object::Object;
+
+#[derive(Clone)]
+pub struct LoxClass {
+    pub name: String,
+    pub superclass: Option<Rc<LoxClass>>,
+    pub methods: HashMap<String, LoxFunc>,
+}
+
+impl LoxClass {
+    // Looks through this class first, then up the superclass chain.
+    pub fn find_method(&self, name: &str) -> Option<&LoxFunc> {
+        match self.methods.get(name) {
+            Some(method) => Some(method),
+            None => self
+                .superclass
+                .as_ref()
+                .and_then(|superclass| superclass.find_method(name)),
+        }
+    }
+
+    // Calling a class creates an instance and runs `init` on it, if any.
+    pub fn call(
+        class: &Rc<LoxClass>,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        let instance = Rc::new(RefCell::new(LoxInstance::new(class)));
+        if let Some(initializer) = class.find_method("init") {
+            initializer
+                .bind(Object::Instance(Rc::clone(&instance)))
+                .call(interpreter, arguments)?;
+        }
+        Ok(Object::Instance(instance))
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self.find_method("init") {
+            Some(initializer) => initializer.arity(),
+            None => Arity::Fixed(0),
+        }
+    }
+}
+
+impl fmt::Debug for LoxClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut methods: Vec<&String> = self.methods.keys().collect();
+        methods.sort();
+        f.debug_struct("LoxClass")
+            .field("name", &self.name)
+            .field(
+                "superclass",
+                &self.superclass.as_ref().map(|superclass| &superclass.name),
+            )
+            .field("methods", &methods)
+            .finish()
+    }
+}
+
+impl fmt::Display for LoxClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Clone)]
+pub struct LoxInstance {
+    pub class: Rc<LoxClass>,
+    fields: HashMap<String, Object>,
+}
+
+impl LoxInstance {
+    pub fn new(class: &Rc<LoxClass>) -> LoxInstance {
+        LoxInstance {
+            class: Rc::clone(class),
+            fields: HashMap::new(),
+        }
+    }
+
+    // Fields shadow methods. Methods are bound to `this` on the way out.
+    pub fn get(instance: &Rc<RefCell<LoxInstance>>, name: &Token) -> Result<Object, RuntimeError> {
+        if let Some(value) = instance.borrow().fields.get(&name.lexeme) {
+            return Ok(value.clone());
+        }
+        let class = Rc::clone(&instance.borrow().class);
+        match class.find_method(&name.lexeme) {
+            Some(method) => Ok(Object::Call(Rc::new(
+                method.bind(Object::Instance(Rc::clone(instance))),
+            ))),
+            None => Err(RuntimeError::new(
+                name.span,
+                format!("Undefined property '{}'.", name.lexeme),
+            )),
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: Object) {
+        self.fields.insert(name.to_string(), value);
+    }
+
+    pub fn field(&self, name: &str) -> Option<Object> {
+        self.fields.get(name).cloned()
+    }
+}
+
+// Instances can reach themselves through their fields, so the derived Debug
+// would never terminate.
+impl fmt::Debug for LoxInstance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fields: Vec<&String> = self.fields.keys().collect();
+        fields.sort();
+        f.debug_struct("LoxInstance")
+            .field("class", &self.class.name)
+            .field("fields", &fields)
+            .finish()
+    }
+}
+
+impl fmt::Display for LoxInstance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} instance", self.class.name)
+    }
+}
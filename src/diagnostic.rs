@@ -0,0 +1,541 @@
+pub use crate::error::{Frame, ParserError, ResolverError, RuntimeError, ScanError};
+pub use crate::token::{Span, TokenType};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => CYAN,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+// Everything the scanner, parser, resolver and interpreter report ends up as
+// one of these, so there is a single place that decides how errors look.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub primary: Label,
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: String, span: Span) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            code: None,
+            message,
+            primary: Label {
+                span,
+                message: String::new(),
+            },
+            secondary: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Diagnostic {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_primary_message(mut self, message: String) -> Diagnostic {
+        self.primary.message = message;
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: String) -> Diagnostic {
+        self.secondary.push(Label { span, message });
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Diagnostic {
+        self.notes.push(note);
+        self
+    }
+}
+
+impl From<ScanError> for Diagnostic {
+    fn from(error: ScanError) -> Self {
+        Diagnostic::error(error.message, error.span).with_code("E0001")
+    }
+}
+
+impl From<ParserError> for Diagnostic {
+    fn from(error: ParserError) -> Self {
+        let label = if error.token.token_type == TokenType::Eof {
+            "at end of input".to_string()
+        } else {
+            format!("at '{}'", error.token.lexeme)
+        };
+        Diagnostic::error(error.message, error.token.span)
+            .with_code("E0002")
+            .with_primary_message(label)
+    }
+}
+
+impl From<ResolverError> for Diagnostic {
+    fn from(error: ResolverError) -> Self {
+        Diagnostic::error(error.message, error.token.span)
+            .with_code("E0003")
+            .with_primary_message(format!("at '{}'", error.token.lexeme))
+    }
+}
+
+impl From<RuntimeError> for Diagnostic {
+    fn from(error: RuntimeError) -> Self {
+        Diagnostic::error(error.message, error.span).with_code("E0004")
+    }
+}
+
+pub struct SourceFile {
+    pub name: String,
+    pub source: String,
+}
+
+// Owns the text of every file or REPL line that has been run, indexed by the
+// `file_id` stored in each `Span`.
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> SourceMap {
+        SourceMap { files: Vec::new() }
+    }
+
+    pub fn add(&mut self, name: String, source: String) -> usize {
+        self.files.push(SourceFile { name, source });
+        self.files.len() - 1
+    }
+
+    pub fn get(&self, file_id: usize) -> Option<&SourceFile> {
+        self.files.get(file_id)
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[1;36m";
+const BLUE: &str = "\x1b[1;34m";
+
+// Identical traceback entries shown in a row before the rest are summarised.
+const MAX_REPEATS: usize = 3;
+
+fn same_entry(a: (Span, &str), b: (Span, &str)) -> bool {
+    a.0 == b.0 && a.1 == b.1
+}
+
+// Prints diagnostics the way rustc does: a header, the offending source line
+// and carets under the span. `plain` is for logs, `colored` for terminals.
+pub struct Renderer {
+    color: bool,
+}
+
+impl Renderer {
+    pub fn plain() -> Renderer {
+        Renderer { color: false }
+    }
+
+    pub fn colored() -> Renderer {
+        Renderer { color: true }
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic, sources: &SourceMap) -> String {
+        let mut out = String::new();
+        let severity = diagnostic.severity;
+
+        let header = match diagnostic.code {
+            Some(code) => format!("{}[{}]", severity.name(), code),
+            None => severity.name().to_string(),
+        };
+        let _ = writeln!(
+            out,
+            "{}: {}",
+            self.paint(severity.color(), &header),
+            self.paint(BOLD, &diagnostic.message)
+        );
+
+        let primary = &diagnostic.primary;
+        // Line 0 is no location at all, e.g. an error from `Lox::call`.
+        let has_location = primary.span.line != 0;
+        let file = match sources.get(primary.span.file_id).filter(|_| has_location) {
+            Some(file) => file,
+            None => {
+                if has_location {
+                    let _ = writeln!(
+                        out,
+                        " --> line {}, column {}",
+                        primary.span.line, primary.span.column
+                    );
+                }
+                self.render_notes(&mut out, diagnostic, 0);
+                return out;
+            }
+        };
+
+        let mut lines: Vec<usize> = std::iter::once(primary)
+            .chain(diagnostic.secondary.iter())
+            .filter(|label| label.span.file_id == primary.span.file_id && label.span.line != 0)
+            .map(|label| label.span.line)
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+        let gutter = lines.last().map_or(1, |line| line.to_string().len());
+
+        let _ = writeln!(
+            out,
+            "{}{} {}:{}:{}",
+            " ".repeat(gutter),
+            self.paint(BLUE, "-->"),
+            file.name,
+            primary.span.line,
+            primary.span.column
+        );
+        let _ = writeln!(out, "{} {}", " ".repeat(gutter), self.paint(BLUE, "|"));
+
+        for line in lines {
+            let text = file.source.lines().nth(line - 1).unwrap_or("");
+            let _ = writeln!(
+                out,
+                "{} {}",
+                self.paint(BLUE, &format!("{:>width$} |", line, width = gutter)),
+                text
+            );
+            if primary.span.line == line {
+                self.render_label(&mut out, primary, text, '^', severity.color(), gutter);
+            }
+            for label in &diagnostic.secondary {
+                if label.span.file_id == primary.span.file_id && label.span.line == line {
+                    self.render_label(&mut out, label, text, '-', BLUE, gutter);
+                }
+            }
+        }
+        self.render_notes(&mut out, diagnostic, gutter);
+        out
+    }
+
+    // Prints a runtime error's Lox call stack the way Python prints a
+    // traceback: one entry per function, most recent call last, each showing
+    // where that function was when the error happened.
+    pub fn render_backtrace(&self, error: &RuntimeError, sources: &SourceMap) -> String {
+        let mut out = String::new();
+        if error.backtrace.is_empty() {
+            return out;
+        }
+        let _ = writeln!(out, "Traceback (most recent call last):");
+
+        let mut entries: Vec<(Span, &str)> = Vec::new();
+        let mut function = "<script>";
+        for frame in error.backtrace.iter() {
+            entries.push((frame.call_site, function));
+            function = &frame.function;
+        }
+        entries.push((error.span, function));
+
+        // Deep recursion would otherwise bury the interesting part, so runs
+        // of the same entry are cut short like Python does.
+        let mut index = 0;
+        while index < entries.len() {
+            let entry = entries[index];
+            let mut run = 1;
+            while index + run < entries.len() && same_entry(entries[index + run], entry) {
+                run += 1;
+            }
+            for _ in 0..run.min(MAX_REPEATS) {
+                self.render_entry(&mut out, entry.0, entry.1, sources);
+            }
+            if run > MAX_REPEATS {
+                let _ = writeln!(
+                    out,
+                    "  [Previous line repeated {} more times]",
+                    run - MAX_REPEATS
+                );
+            }
+            index += run;
+        }
+        out
+    }
+
+    fn render_entry(&self, out: &mut String, span: Span, function: &str, sources: &SourceMap) {
+        let file = sources.get(span.file_id).filter(|_| span.line != 0);
+        match file {
+            Some(file) => {
+                let _ = writeln!(
+                    out,
+                    "  File \"{}\", line {}, column {}, in {}",
+                    file.name, span.line, span.column, function
+                );
+                if let Some(text) = file.source.lines().nth(span.line - 1) {
+                    let _ = writeln!(out, "    {}", text.trim());
+                }
+            }
+            // Called from Rust through the embedding API.
+            None => {
+                let _ = writeln!(out, "  <host>, in {}", function);
+            }
+        }
+    }
+
+    fn render_label(
+        &self,
+        out: &mut String,
+        label: &Label,
+        text: &str,
+        marker: char,
+        color: &str,
+        gutter: usize,
+    ) {
+        let start = label.span.column.saturating_sub(1);
+        // Spans measure bytes, carets are one per char.
+        let mut bytes = label.span.end - label.span.start;
+        let width = text
+            .chars()
+            .skip(start)
+            .take_while(|c| {
+                let fits = bytes > 0;
+                bytes = bytes.saturating_sub(c.len_utf8());
+                fits
+            })
+            .count()
+            .max(1);
+        let underline = marker.to_string().repeat(width);
+        let message = if label.message.is_empty() {
+            underline
+        } else {
+            format!("{} {}", underline, label.message)
+        };
+        let _ = writeln!(
+            out,
+            "{} {}{}",
+            " ".repeat(gutter),
+            self.paint(BLUE, "|"),
+            format_args!(" {}{}", " ".repeat(start), self.paint(color, &message))
+        );
+    }
+
+    fn render_notes(&self, out: &mut String, diagnostic: &Diagnostic, gutter: usize) {
+        for note in &diagnostic.notes {
+            let _ = writeln!(
+                out,
+                "{} {} {}",
+                " ".repeat(gutter),
+                self.paint(BLUE, "="),
+                format_args!("{} {}", self.paint(BOLD, "note:"), note)
+            );
+        }
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The span of `text` where it first appears on `line` of `source`.
+    fn span(source: &str, line: usize, text: &str) -> Span {
+        let line_start: usize = source
+            .split_inclusive('\n')
+            .take(line - 1)
+            .map(str::len)
+            .sum();
+        let line_text = source[line_start..].lines().next().unwrap();
+        let offset = line_text.find(text).expect("text on the line");
+        Span {
+            file_id: 0,
+            start: line_start + offset,
+            end: line_start + offset + text.len(),
+            line,
+            column: line_text[..offset].chars().count() + 1,
+        }
+    }
+
+    fn render(diagnostic: &Diagnostic, source: &str) -> String {
+        let mut sources = SourceMap::new();
+        sources.add("test.lox".to_string(), source.to_string());
+        Renderer::plain().render(diagnostic, &sources)
+    }
+
+    #[test]
+    fn primary_label() {
+        let source = "print a + nil;";
+        let diagnostic = Diagnostic::error("Bad operands.".to_string(), span(source, 1, "nil"))
+            .with_code("E0004")
+            .with_primary_message("here".to_string());
+        assert_eq!(
+            render(&diagnostic, source),
+            "\
+error[E0004]: Bad operands.
+ --> test.lox:1:11
+  |
+1 | print a + nil;
+  |           ^^^ here
+"
+        );
+    }
+
+    #[test]
+    fn secondary_label_on_the_same_line() {
+        let source = "var total = count + \"s\";";
+        let diagnostic = Diagnostic::error("Bad operands.".to_string(), span(source, 1, "+"))
+            .with_label(span(source, 1, "count"), "a number".to_string());
+        assert_eq!(
+            render(&diagnostic, source),
+            "\
+error: Bad operands.
+ --> test.lox:1:19
+  |
+1 | var total = count + \"s\";
+  |                   ^
+  |             ----- a number
+"
+        );
+    }
+
+    #[test]
+    fn secondary_label_on_an_earlier_line() {
+        let source = "var total = 1;\nprint b;\nvar total = 2;";
+        let diagnostic =
+            Diagnostic::error("Already declared.".to_string(), span(source, 3, "total"))
+                .with_primary_message("redeclared".to_string())
+                .with_label(span(source, 1, "total"), "first declared here".to_string());
+        assert_eq!(
+            render(&diagnostic, source),
+            "\
+error: Already declared.
+ --> test.lox:3:5
+  |
+1 | var total = 1;
+  |     ----- first declared here
+3 | var total = 2;
+  |     ^^^^^ redeclared
+"
+        );
+    }
+
+    #[test]
+    fn notes() {
+        let source = "brake;";
+        let diagnostic = Diagnostic::error("Undefined.".to_string(), span(source, 1, "brake"))
+            .with_note("did you mean 'break'?".to_string());
+        assert_eq!(
+            render(&diagnostic, source),
+            "\
+error: Undefined.
+ --> test.lox:1:1
+  |
+1 | brake;
+  | ^^^^^
+  = note: did you mean 'break'?
+"
+        );
+    }
+
+    // Columns and carets count chars, while spans measure bytes.
+    #[test]
+    fn non_ascii_text_before_the_caret() {
+        let source = "var päivä = \"🌍\" + nil;";
+        let diagnostic = Diagnostic::error("Bad operands.".to_string(), span(source, 1, "nil"))
+            .with_label(span(source, 1, "\"🌍\""), "a string".to_string());
+        assert_eq!(
+            render(&diagnostic, source),
+            "\
+error: Bad operands.
+ --> test.lox:1:19
+  |
+1 | var päivä = \"🌍\" + nil;
+  |                   ^^^
+  |             --- a string
+"
+        );
+    }
+
+    #[test]
+    fn gutter_fits_two_digit_line_numbers() {
+        let source = format!("{}print x;\nprint y;\n", "\n".repeat(8));
+        let diagnostic = Diagnostic::error("Undefined.".to_string(), span(&source, 10, "y"))
+            .with_label(span(&source, 9, "x"), "also undefined".to_string())
+            .with_note("declare them first".to_string());
+        assert_eq!(
+            render(&diagnostic, &source),
+            "\
+error: Undefined.
+  --> test.lox:10:7
+   |
+ 9 | print x;
+   |       - also undefined
+10 | print y;
+   |       ^
+   = note: declare them first
+"
+        );
+    }
+
+    #[test]
+    fn colored_output() {
+        let source = "print nil + 1;";
+        let diagnostic = Diagnostic::error("Bad operands.".to_string(), span(source, 1, "+"))
+            .with_code("E0004")
+            .with_label(span(source, 1, "nil"), "nil".to_string())
+            .with_note("a note".to_string());
+        let mut sources = SourceMap::new();
+        sources.add("test.lox".to_string(), source.to_string());
+
+        let colored = Renderer::colored().render(&diagnostic, &sources);
+        assert!(colored.starts_with(&format!("{}error[E0004]{}: ", RED, RESET)));
+        for painted in [
+            format!("{}Bad operands.{}", BOLD, RESET),
+            format!("{}-->{}", BLUE, RESET),
+            format!("{}^{}", RED, RESET),
+            format!("{}--- nil{}", BLUE, RESET),
+            format!("{}note:{}", BOLD, RESET),
+        ] {
+            assert!(colored.contains(&painted), "{:?} in {:?}", painted, colored);
+        }
+
+        let plain = Renderer::plain().render(&diagnostic, &sources);
+        assert!(!plain.contains('\x1b'), "{:?}", plain);
+        let stripped = colored
+            .replace(RESET, "")
+            .replace(BOLD, "")
+            .replace(RED, "")
+            .replace(BLUE, "");
+        assert_eq!(stripped, plain);
+    }
+}
@@ -1,12 +1,11 @@
 pub use crate::error::RuntimeError;
-pub use crate::expr::{Expr, LiteralValue, Stmt};
 pub use crate::object::Object;
-pub use crate::token::{Token, TokenType};
+pub use crate::token::Token;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Environment {
     values: HashMap<String, Object>,
     enclosing: Option<Rc<RefCell<Environment>>>,
@@ -31,62 +30,87 @@ impl Environment {
         self.values.insert(name, value);
     }
 
-    #[allow(dead_code)]
+    // Looks only at this scope, without touching `enclosing`.
+    pub fn get_local(&self, name: &str) -> Option<Object> {
+        self.values.get(name).cloned()
+    }
+
     pub fn debug_print(&self) {
         println!("Debug print {:?}", self.values);
-        if self.enclosing.is_some() {
-            println!(
-                "Debug print {:?}",
-                self.enclosing.as_ref().unwrap().borrow().values
-            );
-        }
-    }
-    //TODO think about this.
-    fn get_from_enclosing(&self, token: Token) -> Result<Object, RuntimeError> {
-        match &self.enclosing {
-            Some(enclosing) => enclosing.borrow().get(token),
-            None => {
-                return Err(RuntimeError {
-                    token,
-                    message: "Undefined variable. Not found in enclosing.".to_string(),
-                })
-            }
+        if let Some(enclosing) = &self.enclosing {
+            println!("Debug print {:?}", enclosing.borrow().values);
         }
     }
 
     pub fn get(&self, token: Token) -> Result<Object, RuntimeError> {
         match self.values.get(&token.lexeme) {
-            Some(obj) => return Ok(obj.clone()),
-            None => {
-                if self.enclosing.is_some() {
-                    return self.get_from_enclosing(token);
-                } else {
-                    return Err(RuntimeError {
-                        token,
-                        message: "Undefined variable. No enclosing found.".to_string(),
-                    });
-                }
-            }
+            Some(obj) => Ok(obj.clone()),
+            None => match &self.enclosing {
+                Some(enclosing) => enclosing.borrow().get(token),
+                None => Err(undefined(token)),
+            },
         }
     }
 
     pub fn assign(&mut self, token: Token, value: Object) -> Result<(), RuntimeError> {
-        if self.values.contains_key(&token.lexeme) {
+        if let Some(slot) = self.values.get_mut(&token.lexeme) {
+            *slot = value;
+            return Ok(());
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(token, value),
+            None => Err(undefined(token)),
+        }
+    }
+
+    // The resolver has already checked that the variable exists `distance`
+    // scopes away, so these skip the by-name walk through `enclosing`.
+    pub fn get_at(&self, distance: usize, token: Token) -> Result<Object, RuntimeError> {
+        if distance == 0 {
+            return match self.values.get(&token.lexeme) {
+                Some(obj) => Ok(obj.clone()),
+                None => Err(undefined(token)),
+            };
+        }
+        self.ancestor(distance).borrow().get_at(0, token)
+    }
+
+    pub fn assign_at(
+        &mut self,
+        distance: usize,
+        token: Token,
+        value: Object,
+    ) -> Result<(), RuntimeError> {
+        if distance == 0 {
             self.values.insert(token.lexeme, value);
             return Ok(());
         }
-        if self.enclosing.is_some() {
-            return self
-                .enclosing
+        self.ancestor(distance)
+            .borrow_mut()
+            .assign_at(0, token, value)
+    }
+
+    fn ancestor(&self, distance: usize) -> Rc<RefCell<Environment>> {
+        let mut environment = Rc::clone(
+            self.enclosing
                 .as_ref()
-                .unwrap()
-                .borrow_mut()
-                .assign(token, value);
+                .expect("Resolved scope depth is deeper than the environment chain"),
+        );
+        for _ in 1..distance {
+            let enclosing = Rc::clone(
+                environment
+                    .borrow()
+                    .enclosing
+                    .as_ref()
+                    .expect("Resolved scope depth is deeper than the environment chain"),
+            );
+            environment = enclosing;
         }
-
-        return Err(RuntimeError {
-            token,
-            message: "Undefined variable. Could not assign.".to_string(),
-        });
+        environment
     }
 }
+
+fn undefined(token: Token) -> RuntimeError {
+    let message = format!("Undefined variable '{}'.", token.lexeme);
+    RuntimeError::new(token.span, message)
+}
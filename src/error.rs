@@ -1,32 +1,130 @@
 use std::fmt;
+use std::rc::Rc;
 
 use crate::callable::Object;
-pub use crate::token::{Token, TokenType};
+use crate::diagnostic::Diagnostic;
+pub use crate::token::{Span, Token, TokenType};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct ScanError {
+    pub span: Span,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
 pub struct ParserError {
     pub token: Token,
     pub message: String,
 }
 
-#[derive(Debug)]
-pub struct RuntimeError {
+#[derive(Debug, Clone)]
+pub struct ResolverError {
     pub token: Token,
     pub message: String,
 }
 
+// A Lox function that was running when a runtime error was raised, and where
+// it was called from.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub function: String,
+    pub call_site: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub span: Span,
+    pub message: String,
+    // Outermost call first. Empty for errors raised in top-level code. Behind
+    // an `Rc` to keep the error small, it's returned from nearly everywhere.
+    pub backtrace: Rc<Vec<Frame>>,
+    // What a `throw` statement threw, handed to `catch` as is. Errors the
+    // interpreter raises itself have none.
+    pub value: Option<Rc<Object>>,
+    // Set by the `exit` native. The error unwinds the whole script, past any
+    // `catch`, and the host decides what exiting means.
+    pub exit: Option<i32>,
+}
+
+impl RuntimeError {
+    pub fn new(span: Span, message: String) -> RuntimeError {
+        RuntimeError {
+            span,
+            message,
+            backtrace: Rc::new(Vec::new()),
+            value: None,
+            exit: None,
+        }
+    }
+
+    // Uncaught, a thrown error reports the value's `message` field if it has
+    // one, so instances of `Error` read the same as built-in errors.
+    pub fn thrown(token: Token, value: Object) -> RuntimeError {
+        let message = match &value {
+            Object::String(message) => message.clone(),
+            Object::Instance(instance) => match instance.borrow().field("message") {
+                Some(Object::String(message)) => message,
+                _ => value.to_string(),
+            },
+            _ => value.to_string(),
+        };
+        RuntimeError {
+            value: Some(Rc::new(value)),
+            ..RuntimeError::new(token.span, message)
+        }
+    }
+
+    // For native functions, which have no span of their own. The interpreter
+    // points the error at the call site once it comes back out.
+    pub fn native(message: String) -> RuntimeError {
+        RuntimeError::new(Span::default(), message)
+    }
+
+    pub fn exit(code: i32) -> RuntimeError {
+        RuntimeError {
+            exit: Some(code),
+            ..RuntimeError::native(format!("Exited with code {}.", code))
+        }
+    }
+
+    pub fn has_location(&self) -> bool {
+        self.span.line != 0
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
-    ReturnError { value: Object },
-    RuntimeError { token: Token, message: String },
+    // The spans are the `return`, `break` or `continue` keyword's.
+    ReturnError { value: Object, span: Span },
+    RuntimeError(RuntimeError),
+    // Unwind to the innermost enclosing loop.
+    Break { span: Span },
+    Continue { span: Span },
+}
+
+impl Error {
+    // The resolver rejects `return` outside of a function and the parser
+    // `break` and `continue` outside of a loop, but code that drives the
+    // passes by hand can skip them. Then the statement fails at runtime.
+    pub fn into_runtime_error(self) -> RuntimeError {
+        match self {
+            Error::RuntimeError(error) => error,
+            Error::ReturnError { span, .. } => {
+                RuntimeError::new(span, "Can't return from top-level code.".to_string())
+            }
+            Error::Break { span } => {
+                RuntimeError::new(span, "Can't use 'break' outside of a loop.".to_string())
+            }
+            Error::Continue { span } => {
+                RuntimeError::new(span, "Can't use 'continue' outside of a loop.".to_string())
+            }
+        }
+    }
 }
 
 impl From<RuntimeError> for Error {
     fn from(runtime_error: RuntimeError) -> Self {
-        Error::RuntimeError {
-            token: runtime_error.token,
-            message: runtime_error.message,
-        }
+        Error::RuntimeError(runtime_error)
     }
 }
 
@@ -34,17 +132,18 @@ impl From<ReturnError> for Error {
     fn from(error: ReturnError) -> Self {
         Error::ReturnError {
             value: (error.value),
+            span: error.span,
         }
     }
 }
 
-impl<'a> fmt::Display for Error {
+impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Error::ReturnError { value } => write!(f, "Value: {}", value),
-            Error::RuntimeError { token, message } => {
-                write!(f, "Token: {}, message: {}", token, message)
-            }
+            Error::ReturnError { value, .. } => write!(f, "Value: {}", value),
+            Error::RuntimeError(error) => write!(f, "{}", error),
+            Error::Break { .. } => write!(f, "break"),
+            Error::Continue { .. } => write!(f, "continue"),
         }
     }
 }
@@ -52,10 +151,119 @@ impl<'a> fmt::Display for Error {
 #[derive(Debug)]
 pub struct ReturnError {
     pub value: Object,
+    pub span: Span,
 }
-// TODO reporting needs to be implemented
-#[allow(dead_code)]
-fn report(line: u32, location: String, message: String) {
-    println!("[line: {}] error {}: {}", line, location, message);
-    //HAD_ERROR = true;
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[line {}, column {}] Error: {}",
+            self.span.line, self.span.column, self.message
+        )
+    }
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        report(f, &self.token, &self.message)
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[line {}, column {}] {}",
+            self.span.line, self.span.column, self.message
+        )
+    }
+}
+
+impl fmt::Display for ResolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        report(f, &self.token, &self.message)
+    }
+}
+
+fn report(f: &mut fmt::Formatter<'_>, token: &Token, message: &str) -> fmt::Result {
+    let span = token.span;
+    if token.token_type == TokenType::Eof {
+        write!(
+            f,
+            "[line {}, column {}] Error at end: {}",
+            span.line, span.column, message
+        )
+    } else {
+        write!(
+            f,
+            "[line {}, column {}] Error at '{}': {}",
+            span.line, span.column, token.lexeme, message
+        )
+    }
+}
+
+// Everything that can go wrong when the embedding API runs a script.
+#[derive(Debug)]
+pub enum LoxError {
+    Io(std::io::Error),
+    Scan(Vec<ScanError>),
+    Parse(Vec<ParserError>),
+    Resolve(Vec<ResolverError>),
+    Runtime(RuntimeError),
+    // The script called `exit` with this code.
+    Exit(i32),
+}
+
+impl LoxError {
+    // Io errors and exits have no location in any source, so they produce
+    // none.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            LoxError::Io(_) | LoxError::Exit(_) => Vec::new(),
+            LoxError::Scan(errors) => errors.iter().cloned().map(Diagnostic::from).collect(),
+            LoxError::Parse(errors) => errors.iter().cloned().map(Diagnostic::from).collect(),
+            LoxError::Resolve(errors) => errors.iter().cloned().map(Diagnostic::from).collect(),
+            LoxError::Runtime(error) => vec![error.clone().into()],
+        }
+    }
+}
+
+impl fmt::Display for LoxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoxError::Io(error) => write!(f, "{}", error),
+            LoxError::Scan(errors) => write_all(f, errors),
+            LoxError::Parse(errors) => write_all(f, errors),
+            LoxError::Resolve(errors) => write_all(f, errors),
+            LoxError::Runtime(error) => write!(f, "{}", error),
+            LoxError::Exit(code) => write!(f, "Exited with code {}.", code),
+        }
+    }
+}
+
+fn write_all<T: fmt::Display>(f: &mut fmt::Formatter<'_>, errors: &[T]) -> fmt::Result {
+    for (i, error) in errors.iter().enumerate() {
+        if i > 0 {
+            writeln!(f)?;
+        }
+        write!(f, "{}", error)?;
+    }
+    Ok(())
+}
+
+impl std::error::Error for LoxError {}
+
+impl From<std::io::Error> for LoxError {
+    fn from(error: std::io::Error) -> Self {
+        LoxError::Io(error)
+    }
+}
+
+impl From<RuntimeError> for LoxError {
+    fn from(error: RuntimeError) -> Self {
+        match error.exit {
+            Some(code) => LoxError::Exit(code),
+            None => LoxError::Runtime(error),
+        }
+    }
 }
@@ -1,4 +1,4 @@
-pub use crate::token::Token;
+pub use crate::token::{Span, Token};
 use std::fmt;
 // TODO implement some of the methods.
 // evaluate, accept, execute
@@ -16,26 +16,97 @@ pub enum Expr {
     },
     Literal {
         literal_value: LiteralValue,
+        span: Span,
     },
     Logical {
         left: Box<Expr>,
         operator: Token,
         right: Box<Expr>,
     },
+    // `depth` is filled in by the resolver: the number of scopes between the
+    // use and the declaration, or `None` for globals.
     Variable {
         token: Token,
+        depth: Option<usize>,
     },
     Assign {
         name: Token,
         value: Box<Expr>,
+        depth: Option<usize>,
     },
     Grouping {
         group: Box<Expr>,
+        span: Span,
     },
     Call {
         callee: Box<Expr>,
         paren: Token,
-        arguments: Box<Vec<Expr>>,
+        arguments: Vec<Expr>,
+    },
+    Get {
+        object: Box<Expr>,
+        name: Token,
+    },
+    // `operator` is set for compound assignments like `a.b += 1`, so `a` is
+    // only evaluated once. On a variable they're plain `Assign`s instead.
+    Set {
+        object: Box<Expr>,
+        name: Token,
+        value: Box<Expr>,
+        operator: Option<Token>,
+    },
+    List {
+        elements: Vec<Expr>,
+        span: Span,
+    },
+    // `{key: value, ...}`. Keys are expressions, evaluated like values.
+    Map {
+        entries: Vec<(Expr, Expr)>,
+        span: Span,
+    },
+    // `bracket` is the `[`, where out of bounds errors point.
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    Slice {
+        object: Box<Expr>,
+        bracket: Token,
+        start: Option<Box<Expr>>,
+        end: Option<Box<Expr>>,
+    },
+    // `xs[i] = value`, with `operator` set for compound assignments like
+    // `Set`.
+    SetIndex {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+        operator: Option<Token>,
+    },
+    // `++` or `--` on a `Variable`, `Get` or `Index` target, before or after
+    // it.
+    Update {
+        target: Box<Expr>,
+        operator: Token,
+        prefix: bool,
+    },
+    This {
+        keyword: Token,
+        depth: Option<usize>,
+    },
+    Super {
+        keyword: Token,
+        method: Token,
+        depth: Option<usize>,
+    },
+    // `fun (a) { ... }` or `(a) => ...`. `keyword` is the `fun` or the `=>`.
+    // An arrow with an expression body gets a single `return` statement.
+    Lambda {
+        keyword: Token,
+        params: Vec<Token>,
+        body: Vec<Stmt>,
     },
 }
 
@@ -46,6 +117,38 @@ pub enum LiteralValue {
     Boolean(bool),
     Null,
 }
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                left.span().to(right.span())
+            }
+            Expr::Unary { operator, right } => operator.span.to(right.span()),
+            Expr::Literal { span, .. } | Expr::Grouping { span, .. } => *span,
+            Expr::Variable { token, .. } => token.span,
+            Expr::Assign { name, value, .. } => name.span.to(value.span()),
+            Expr::Call { callee, paren, .. } => callee.span().to(paren.span),
+            Expr::Get { object, name } => object.span().to(name.span),
+            Expr::Set { object, value, .. } | Expr::SetIndex { object, value, .. } => {
+                object.span().to(value.span())
+            }
+            Expr::List { span, .. } | Expr::Map { span, .. } => *span,
+            Expr::Index { object, index, .. } => object.span().to(index.span()),
+            Expr::Slice {
+                object, bracket, ..
+            } => object.span().to(bracket.span),
+            Expr::Update {
+                target, operator, ..
+            } => operator.span.to(target.span()),
+            Expr::This { keyword, .. } => keyword.span,
+            Expr::Super {
+                keyword, method, ..
+            } => keyword.span.to(method.span),
+            Expr::Lambda { keyword, .. } => keyword.span,
+        }
+    }
+}
+
 impl fmt::Display for Expr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)
@@ -53,31 +156,15 @@ impl fmt::Display for Expr {
 }
 
 #[derive(Debug, Clone)]
-pub struct Var {
-    pub name: Token,
-    pub initializer: Option<Expr>,
-}
-
-#[derive(Debug, Clone)]
-pub struct IfStmt {
-    pub condition: Box<Expr>,
-    pub then_branch: Box<Stmt>,
-    pub else_branch: Option<Box<Stmt>>,
-}
-
-#[derive(Debug, Clone)]
-pub struct WhileStmt {
-    pub condition: Box<Expr>,
-    pub body: Box<Stmt>,
-}
-
-#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Stmt {
     Expression {
         expr: Expr,
+        span: Span,
     },
     Print {
         expr: Expr,
+        span: Span,
     },
     Return {
         keyword: Token,
@@ -89,19 +176,74 @@ pub enum Stmt {
     },
     Block {
         statements: Vec<Stmt>,
+        span: Span,
     },
     IfStmt {
         condition: Expr,
         then_branch: Box<Stmt>,
         else_branch: Option<Box<Stmt>>,
+        span: Span,
     },
+    // `increment` is only set for a desugared `for` loop. It runs after
+    // every pass through `body`, including one cut short by `continue`.
     WhileStmt {
         condition: Expr,
         body: Box<Stmt>,
+        increment: Option<Expr>,
+        span: Span,
+    },
+    Break {
+        keyword: Token,
+    },
+    Continue {
+        keyword: Token,
     },
     Function {
         name: Token,
         params: Vec<Token>,
         body: Vec<Stmt>,
     },
+    // Every entry in `methods` is a `Stmt::Function`. `superclass` is always
+    // an `Expr::Variable`.
+    Class {
+        name: Token,
+        superclass: Option<Expr>,
+        methods: Vec<Stmt>,
+    },
+    Throw {
+        keyword: Token,
+        value: Expr,
+    },
+    // `catch` is the name the error is bound to and the statements handling
+    // it. At least one of `catch` and `finally` is present.
+    Try {
+        body: Vec<Stmt>,
+        catch: Option<(Token, Vec<Stmt>)>,
+        finally: Option<Vec<Stmt>>,
+        span: Span,
+    },
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Expression { span, .. }
+            | Stmt::Print { span, .. }
+            | Stmt::Block { span, .. }
+            | Stmt::IfStmt { span, .. }
+            | Stmt::WhileStmt { span, .. }
+            | Stmt::Try { span, .. } => *span,
+            Stmt::Return { keyword, value } => match value {
+                Some(value) => keyword.span.to(value.span()),
+                None => keyword.span,
+            },
+            Stmt::Var { name, initializer } => match initializer {
+                Some(initializer) => name.span.to(initializer.span()),
+                None => name.span,
+            },
+            Stmt::Function { name, .. } | Stmt::Class { name, .. } => name.span,
+            Stmt::Break { keyword } | Stmt::Continue { keyword } => keyword.span,
+            Stmt::Throw { keyword, value } => keyword.span.to(value.span()),
+        }
+    }
 }
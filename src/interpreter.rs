@@ -1,64 +1,89 @@
-use crate::callable::LoxFunc;
+use crate::callable::{Arity, LoxFunc};
+use crate::class::{LoxClass, LoxInstance};
 pub use crate::environment::Environment;
-use crate::error::Error;
 pub use crate::error::RuntimeError;
+use crate::error::{Error, Frame};
 pub use crate::expr::{Expr, LiteralValue, Stmt};
+use crate::list;
+use crate::map::{self, LoxMap};
 pub use crate::object::Object;
+use crate::prelude;
 pub use crate::token::{Token, TokenType};
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
-use std::time::{SystemTime, UNIX_EPOCH};
+
+// Calls nested deeper than this fail with "Stack overflow.", like jlox's
+// StackOverflowError but catchable, and without taking the host down.
+const MAX_FRAMES: usize = 1000;
+
+// Each Lox call nests a dozen Rust frames, tens of kilobytes in a debug
+// build. Before a call, the stack is grown onto the heap when less than
+// `STACK_RED_ZONE` is left, so `MAX_FRAMES` holds on any thread, e.g. a test
+// harness's small one.
+const STACK_RED_ZONE: usize = 256 * 1024;
+const STACK_GROWTH: usize = 4 * 1024 * 1024;
 
 pub struct Interpreter {
     pub globals: Rc<RefCell<Environment>>,
     pub environment: Rc<RefCell<Environment>>,
+    // Lox functions currently being called, outermost first.
+    frames: Vec<Frame>,
+    // Class of the values `catch` binds for errors the interpreter raised.
+    pub error_class: Rc<LoxClass>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Interpreter {
-        Interpreter {
-            globals: Rc::new(RefCell::new(Environment::new())),
-            environment: Rc::new(RefCell::new(Environment::new())),
-        }
-    }
-
-    #[allow(dead_code)]
-    fn get_clock() -> Object {
-        let clock = Object::Call(LoxFunc::Callable {
-            arity: 0,
-            // ignore args, return new number object.
-            func: Box::new(|_: Vec<Object>| {
-                Object::Number(
-                    (SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .expect("Somehow the time broke")
-                        .as_millis()) as f64,
-                )
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        let mut interpreter = Interpreter {
+            environment: Rc::clone(&globals),
+            globals,
+            frames: Vec::new(),
+            error_class: Rc::new(LoxClass {
+                name: "Error".to_string(),
+                superclass: None,
+                methods: HashMap::new(),
             }),
-        });
-        return clock;
+        };
+        prelude::install(&mut interpreter);
+        interpreter
     }
+
     // Really refactor this
     fn interpret_stmt(&mut self, stmt: Stmt) -> Result<(), Error> {
         match stmt {
-            Stmt::Expression { .. } => self.visit_expression_stmt(stmt),
-            Stmt::Print { .. } => self.visit_print_stmt(stmt),
+            Stmt::Expression { .. } => self.visit_expression_stmt(stmt)?,
+            Stmt::Print { .. } => self.visit_print_stmt(stmt)?,
             Stmt::Return { .. } => self.visit_return_stmt(stmt)?,
-            Stmt::Var { .. } => self.visit_var_stmt(stmt),
+            Stmt::Var { .. } => self.visit_var_stmt(stmt)?,
             Stmt::Block { .. } => self.visit_block_stmt(stmt)?,
             Stmt::IfStmt { .. } => self.visit_if_stmt(stmt)?,
             Stmt::WhileStmt { .. } => self.visit_while_stmt(stmt)?,
             Stmt::Function { .. } => self.visit_function_stmt(stmt),
+            Stmt::Class { .. } => self.visit_class_stmt(stmt)?,
+            Stmt::Throw { .. } => self.visit_throw_stmt(stmt)?,
+            Stmt::Try { .. } => self.visit_try_stmt(stmt)?,
+            Stmt::Break { keyword } => return Err(Error::Break { span: keyword.span }),
+            Stmt::Continue { keyword } => return Err(Error::Continue { span: keyword.span }),
         }
-        return Ok(());
+        Ok(())
     }
 
-    pub fn interpret_stmts(&mut self, statements: Vec<Stmt>) {
+    pub fn interpret_stmts(&mut self, statements: Vec<Stmt>) -> Result<(), RuntimeError> {
         for stmt in statements.into_iter() {
-            if let Err(err) = self.interpret_stmt(stmt) {
-                panic!("{}", err)
+            match self.interpret_stmt(stmt) {
+                Ok(()) => (),
+                Err(error) => return Err(error.into_runtime_error()),
             }
         }
+        Ok(())
     }
 
     pub fn interpret(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
@@ -71,40 +96,41 @@ impl Interpreter {
             Expr::Unary {
                 operator: _,
                 right: _,
-            } => Ok(self.visit_unary_expr(expr)),
-            Expr::Literal { literal_value: _ } => Ok(self.visit_literal_expr(expr)),
-            Expr::Variable { token: _ } => self.visit_var_expr(expr),
-            Expr::Assign { name: _, value: _ } => self.visit_assign_expr(expr),
+            } => self.visit_unary_expr(expr),
+            Expr::Literal { .. } => Ok(self.visit_literal_expr(expr)),
+            Expr::Variable { .. } => self.visit_var_expr(expr),
+            Expr::Assign { .. } => self.visit_assign_expr(expr),
             Expr::Logical {
                 left: _,
                 operator: _,
                 right: _,
-            } => Ok(self.visit_logical_expr(expr)),
+            } => self.visit_logical_expr(expr),
             Expr::Call {
                 callee: _,
                 paren: _,
                 arguments: _,
             } => self.visit_call_expr(expr),
-            _ => Ok(Object::Nil),
+            Expr::Grouping { .. } => self.visit_grouping_expr(expr),
+            Expr::Get { .. } => self.visit_get_expr(expr),
+            Expr::Set { .. } => self.visit_set_expr(expr),
+            Expr::This { .. } => self.visit_this_expr(expr),
+            Expr::Super { .. } => self.visit_super_expr(expr),
+            Expr::Lambda { .. } => Ok(self.visit_lambda_expr(expr)),
+            Expr::Update { .. } => self.visit_update_expr(expr),
+            Expr::List { .. } => self.visit_list_expr(expr),
+            Expr::Map { .. } => self.visit_map_expr(expr),
+            Expr::Index { .. } => self.visit_index_expr(expr),
+            Expr::Slice { .. } => self.visit_slice_expr(expr),
+            Expr::SetIndex { .. } => self.visit_set_index_expr(expr),
         }
     }
 
     fn is_truthy(&self, object: Object) -> bool {
-        match object {
-            Object::Nil => false,
-            Object::Boolean(false) => false,
-            Object::Boolean(true) => true,
-            _ => true,
-        }
+        object.is_truthy()
     }
 
     fn is_truthy_2(&self, object: &Object) -> bool {
-        match object {
-            Object::Nil => false,
-            Object::Boolean(false) => false,
-            Object::Boolean(true) => true,
-            _ => true,
-        }
+        object.is_truthy()
     }
 
     fn literal_to_object(&self, literal_value: LiteralValue) -> Object {
@@ -120,60 +146,80 @@ impl Interpreter {
         match expr {
             Expr::Literal {
                 literal_value: value,
+                ..
             } => self.literal_to_object(value),
             _ => Object::Nil,
         }
     }
 
-    fn visit_logical_expr(&mut self, expr: Expr) -> Object {
+    fn visit_logical_expr(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
         match expr {
             Expr::Logical {
                 left,
                 operator,
                 right,
             } => {
-                let left_object = self.interpret(*left).unwrap().clone();
-                if operator.token_type == TokenType::Or {
-                    if self.is_truthy_2(&left_object) {
-                        return left_object;
-                    }
-                } else {
-                    if !self.is_truthy_2(&left_object) {
-                        return left_object;
-                    }
+                let left_object = self.interpret(*left)?;
+                let left_truthy = self.is_truthy_2(&left_object);
+                if (operator.token_type == TokenType::Or) == left_truthy {
+                    return Ok(left_object);
                 }
-                return self.interpret(*right).unwrap();
+                self.interpret(*right)
             }
-            _ => Object::Nil,
+            _ => Ok(Object::Nil),
         }
     }
 
-    fn object_number(&self, object: Object) -> f64 {
-        match object {
-            Object::Number(x) => x,
-            _ => 0.0,
+    fn visit_grouping_expr(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
+        match expr {
+            Expr::Grouping { group, .. } => self.interpret(*group),
+            _ => Ok(Object::Nil),
         }
     }
 
-    fn is_equal(&self, a: Object, b: Object) -> bool {
-        match (a, b) {
-            (Object::Number(a), Object::Number(b)) => a == b,
-            (Object::String(a), Object::String(b)) => a == b,
-            (Object::Nil, Object::Nil) => true,
-            (Object::Nil, _) => false,
-            _ => false,
+    // Numbers and strings can be ordered, see `Object::partial_cmp`.
+    fn comparison(
+        &self,
+        operator: Token,
+        left: &Object,
+        right: &Object,
+    ) -> Result<Object, RuntimeError> {
+        if !matches!(
+            (left, right),
+            (Object::Number(_), Object::Number(_)) | (Object::String(_), Object::String(_))
+        ) {
+            return Err(RuntimeError::new(
+                operator.span,
+                "Operands must be two numbers or two strings.".to_string(),
+            ));
         }
+        Ok(Object::Boolean(match operator.token_type {
+            TokenType::Greater => left > right,
+            TokenType::GreaterEqual => left >= right,
+            TokenType::Less => left < right,
+            _ => left <= right,
+        }))
     }
 
-    fn addition(&self, a: Object, b: Object) -> Object {
+    fn addition(&self, operator: Token, a: Object, b: Object) -> Result<Object, RuntimeError> {
         match (a, b) {
             (Object::Number(left_value), Object::Number(right_value)) => {
-                return Object::Number(left_value + right_value);
+                Ok(Object::Number(left_value + right_value))
             }
-            (Object::String(left_value), Object::String(right_value)) => {
-                return Object::String(format!("{}{}", left_value, right_value));
+            // A string turns the other operand into text, so `"n = " + 1` works.
+            (left_value @ Object::String(_), right_value)
+            | (left_value, right_value @ Object::String(_)) => Ok(Object::String(format!(
+                "{}{}",
+                left_value.stringify(),
+                right_value.stringify()
+            ))),
+            (Object::List(left_value), Object::List(right_value)) => {
+                Ok(list::concatenate(&left_value, &right_value))
             }
-            _ => Object::Nil,
+            _ => Err(RuntimeError::new(
+                operator.span,
+                "Operands must be two numbers or two lists, or include a string.".to_string(),
+            )),
         }
     }
 
@@ -186,54 +232,53 @@ impl Interpreter {
             } => {
                 let left_value: Object = self.interpret(*left)?;
                 let right_value: Object = self.interpret(*right)?;
+                self.binary(operator, left_value, right_value)
+            }
+            _ => Ok(Object::Nil),
+        }
+    }
 
-                match operator.token_type {
-                    TokenType::Minus => {
-                        self.check_number_operands(operator, &left_value, &right_value);
-                        let left_number = self.object_number(left_value);
-                        let right_number = self.object_number(right_value);
-                        return Ok(Object::Number(left_number - right_number));
-                    }
-                    TokenType::Plus => return Ok(self.addition(left_value, right_value)),
-                    TokenType::Slash => {
-                        let left_number = self.object_number(left_value);
-                        let right_number = self.object_number(right_value);
-                        return Ok(Object::Number(left_number / right_number));
-                    }
-                    TokenType::Star => {
-                        let left_number = self.object_number(left_value);
-                        let right_number = self.object_number(right_value);
-                        return Ok(Object::Number(left_number * right_number));
-                    }
-                    TokenType::Greater => {
-                        let left_number = self.object_number(left_value);
-                        let right_number = self.object_number(right_value);
-                        return Ok(Object::Boolean(left_number > right_number));
-                    }
-                    TokenType::GreaterEqual => {
-                        let left_number = self.object_number(left_value);
-                        let right_number = self.object_number(right_value);
-                        return Ok(Object::Boolean(left_number >= right_number));
-                    }
-                    TokenType::Less => {
-                        let left_number = self.object_number(left_value);
-                        let right_number = self.object_number(right_value);
-                        return Ok(Object::Boolean(left_number < right_number));
-                    }
-                    TokenType::LessEqual => {
-                        let left_number = self.object_number(left_value);
-                        let right_number = self.object_number(right_value);
-                        return Ok(Object::Boolean(left_number <= right_number));
-                    }
-                    TokenType::BangEqual => {
-                        return Ok(Object::Boolean(!self.is_equal(left_value, right_value)));
-                    }
-                    TokenType::EqualEqual => {
-                        return Ok(Object::Boolean(self.is_equal(left_value, right_value)));
-                    }
-                    _ => return Ok(Object::Nil),
-                }
+    // Shared with compound assignment to a property.
+    fn binary(
+        &self,
+        operator: Token,
+        left_value: Object,
+        right_value: Object,
+    ) -> Result<Object, RuntimeError> {
+        match operator.token_type {
+            TokenType::Plus => self.addition(operator, left_value, right_value),
+            TokenType::Minus => {
+                let (left, right) =
+                    self.check_number_operands(operator, &left_value, &right_value)?;
+                Ok(Object::Number(left - right))
+            }
+            TokenType::Slash => {
+                let (left, right) =
+                    self.check_number_operands(operator, &left_value, &right_value)?;
+                Ok(Object::Number(left / right))
+            }
+            TokenType::Star => {
+                let (left, right) =
+                    self.check_number_operands(operator, &left_value, &right_value)?;
+                Ok(Object::Number(left * right))
+            }
+            // Takes the sign of the left operand, like C's fmod.
+            TokenType::Percent => {
+                let (left, right) =
+                    self.check_number_operands(operator, &left_value, &right_value)?;
+                Ok(Object::Number(left % right))
+            }
+            TokenType::StarStar => {
+                let (left, right) =
+                    self.check_number_operands(operator, &left_value, &right_value)?;
+                Ok(Object::Number(left.powf(right)))
             }
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => self.comparison(operator, &left_value, &right_value),
+            TokenType::BangEqual => Ok(Object::Boolean(left_value != right_value)),
+            TokenType::EqualEqual => Ok(Object::Boolean(left_value == right_value)),
             _ => Ok(Object::Nil),
         }
     }
@@ -254,62 +299,459 @@ impl Interpreter {
 
                 let args = argument_objects?;
 
-                // TODO check this
-                match callee_value {
-                    Object::Call(callable) => {
-                        let arguments_len = args.len();
-                        if callable.arity() != arguments_len {
-                            return Err(RuntimeError {
-                                token: paren,
-                                message: "Something went wrong with the callable".to_string(),
-                            });
+                self.call_value(callee_value, args, &paren)
+            }
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    // Calls a function or class value. `paren` is where errors are reported,
+    // including errors a native function raised without a location.
+    pub fn call_value(
+        &mut self,
+        callee: Object,
+        args: Vec<Object>,
+        paren: &Token,
+    ) -> Result<Object, RuntimeError> {
+        match callee {
+            Object::Call(callable) => match callable.as_ref() {
+                LoxFunc::Native { name, arity, func } => {
+                    let result = if arity.accepts(args.len()) {
+                        func(self, args)
+                    } else {
+                        Err(RuntimeError::native(arity_message(*arity, args.len())))
+                    };
+                    // Native errors carry the function's name so the user
+                    // can tell which builtin complained.
+                    result.map_err(|error| {
+                        if error.has_location() {
+                            error
+                        } else {
+                            RuntimeError {
+                                span: paren.span,
+                                message: format!("{}(): {}", name, error.message),
+                                ..error
+                            }
+                        }
+                    })
+                }
+                LoxFunc::Function { .. } => {
+                    self.check_arity(paren, callable.arity(), args.len())?;
+                    self.with_frame(callable.name(), paren, |interpreter| {
+                        callable.call(interpreter, args)
+                    })
+                }
+            },
+            Object::Class(class) => {
+                self.check_arity(paren, class.arity(), args.len())?;
+                self.with_frame(&class.name, paren, |interpreter| {
+                    LoxClass::call(&class, interpreter, args)
+                })
+            }
+            _ => Err(RuntimeError::new(
+                paren.span,
+                "Can only call functions and classes.".to_string(),
+            )),
+        }
+    }
+
+    // Runs `call` with a frame for `function` pushed. The first frame an error
+    // passes through records the whole stack as the error's backtrace.
+    fn with_frame<F>(
+        &mut self,
+        function: &str,
+        paren: &Token,
+        call: F,
+    ) -> Result<Object, RuntimeError>
+    where
+        F: FnOnce(&mut Interpreter) -> Result<Object, RuntimeError>,
+    {
+        if self.frames.len() >= MAX_FRAMES {
+            return Err(RuntimeError::new(paren.span, "Stack overflow.".to_string()));
+        }
+        self.frames.push(Frame {
+            function: function.to_string(),
+            call_site: paren.span,
+        });
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || call(self));
+        let result = result.map_err(|mut error| {
+            if error.backtrace.is_empty() {
+                error.backtrace = Rc::new(self.frames.clone());
+            }
+            error
+        });
+        self.frames.pop();
+        result
+    }
+
+    pub fn define_native<F>(&mut self, name: &str, arity: Arity, func: F)
+    where
+        F: Fn(&mut Interpreter, Vec<Object>) -> Result<Object, RuntimeError> + 'static,
+    {
+        self.globals.borrow_mut().define(
+            name.to_string(),
+            Object::Call(Rc::new(LoxFunc::native(name, arity, func))),
+        );
+    }
+
+    fn check_arity(&self, paren: &Token, arity: Arity, count: usize) -> Result<(), RuntimeError> {
+        if !arity.accepts(count) {
+            return Err(RuntimeError::new(paren.span, arity_message(arity, count)));
+        }
+        Ok(())
+    }
+
+    fn visit_get_expr(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
+        match expr {
+            Expr::Get { object, name } => match self.interpret(*object)? {
+                Object::Instance(instance) => LoxInstance::get(&instance, &name),
+                Object::List(list) => list::method(&list, &name),
+                Object::Map(map) => map::method(&map, &name),
+                _ => Err(RuntimeError::new(
+                    name.span,
+                    "Only instances have properties.".to_string(),
+                )),
+            },
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
+        match expr {
+            Expr::Set {
+                object,
+                name,
+                value,
+                operator,
+            } => match self.interpret(*object)? {
+                Object::Instance(instance) => {
+                    let value = match operator {
+                        Some(operator) => {
+                            let current = LoxInstance::get(&instance, &name)?;
+                            let value = self.interpret(*value)?;
+                            self.binary(operator, current, value)?
+                        }
+                        None => self.interpret(*value)?,
+                    };
+                    instance.borrow_mut().set(&name.lexeme, value.clone());
+                    Ok(value)
+                }
+                _ => Err(RuntimeError::new(
+                    name.span,
+                    "Only instances have fields.".to_string(),
+                )),
+            },
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    fn visit_list_expr(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
+        match expr {
+            Expr::List { elements, .. } => {
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.interpret(element)?);
+                }
+                Ok(list::new(values))
+            }
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    fn visit_map_expr(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
+        match expr {
+            Expr::Map { entries, .. } => {
+                let mut map = LoxMap::default();
+                for (key, value) in entries {
+                    let span = key.span();
+                    let key = self.interpret(key)?;
+                    let value = self.interpret(value)?;
+                    map.insert(key, value, span)?;
+                }
+                Ok(map::new(map))
+            }
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
+        match expr {
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object = self.interpret(*object)?;
+                let index = self.interpret(*index)?;
+                self.get_index(&object, &index, &bracket)
+            }
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    fn visit_slice_expr(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
+        match expr {
+            Expr::Slice {
+                object,
+                bracket,
+                start,
+                end,
+            } => {
+                let object = self.interpret(*object)?;
+                let start = match start {
+                    Some(start) => Some(self.interpret(*start)?),
+                    None => None,
+                };
+                let end = match end {
+                    Some(end) => Some(self.interpret(*end)?),
+                    None => None,
+                };
+                match object {
+                    Object::List(elements) => list::slice(&elements, start, end, bracket.span),
+                    other => Err(RuntimeError::new(
+                        bracket.span,
+                        format!("Can't slice a {}.", other.type_name()),
+                    )),
+                }
+            }
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    fn visit_set_index_expr(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
+        match expr {
+            Expr::SetIndex {
+                object,
+                bracket,
+                index,
+                value,
+                operator,
+            } => {
+                let object = self.interpret(*object)?;
+                let index = self.interpret(*index)?;
+                let value = match operator {
+                    Some(operator) => {
+                        let current = self.get_index(&object, &index, &bracket)?;
+                        let value = self.interpret(*value)?;
+                        self.binary(operator, current, value)?
+                    }
+                    None => self.interpret(*value)?,
+                };
+                self.set_index(&object, index, value.clone(), &bracket)?;
+                Ok(value)
+            }
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    fn get_index(
+        &self,
+        object: &Object,
+        index: &Object,
+        bracket: &Token,
+    ) -> Result<Object, RuntimeError> {
+        match object {
+            Object::List(elements) => {
+                let elements = elements.borrow();
+                let position = list::index(index, elements.len(), bracket.span)?;
+                Ok(elements[position].clone())
+            }
+            Object::Map(map) => map::get(map, index, bracket.span),
+            other => Err(RuntimeError::new(
+                bracket.span,
+                format!("Can't index into a {}.", other.type_name()),
+            )),
+        }
+    }
+
+    fn set_index(
+        &self,
+        object: &Object,
+        index: Object,
+        value: Object,
+        bracket: &Token,
+    ) -> Result<(), RuntimeError> {
+        match object {
+            Object::List(elements) => {
+                let mut elements = elements.borrow_mut();
+                let position = list::index(&index, elements.len(), bracket.span)?;
+                elements[position] = value;
+                Ok(())
+            }
+            Object::Map(map) => map.borrow_mut().insert(index, value, bracket.span),
+            other => Err(RuntimeError::new(
+                bracket.span,
+                format!("Can't index into a {}.", other.type_name()),
+            )),
+        }
+    }
+
+    fn visit_update_expr(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
+        match expr {
+            Expr::Update {
+                target,
+                operator,
+                prefix,
+            } => {
+                let delta = if operator.token_type == TokenType::PlusPlus {
+                    1.0
+                } else {
+                    -1.0
+                };
+                let old = match *target {
+                    Expr::Variable { token, depth } => {
+                        let value = self.look_up_variable(token.clone(), depth)?;
+                        let old = self.check_number_operand(operator.clone(), &value)?;
+                        self.assign_variable(token, depth, Object::Number(old + delta))?;
+                        old
+                    }
+                    Expr::Get { object, name } => match self.interpret(*object)? {
+                        Object::Instance(instance) => {
+                            let value = LoxInstance::get(&instance, &name)?;
+                            let old = self.check_number_operand(operator.clone(), &value)?;
+                            instance
+                                .borrow_mut()
+                                .set(&name.lexeme, Object::Number(old + delta));
+                            old
                         }
-                        return callable.call(self, args);
+                        _ => {
+                            return Err(RuntimeError::new(
+                                name.span,
+                                "Only instances have fields.".to_string(),
+                            ))
+                        }
+                    },
+                    Expr::Index {
+                        object,
+                        bracket,
+                        index,
+                    } => {
+                        let object = self.interpret(*object)?;
+                        let index = self.interpret(*index)?;
+                        let value = self.get_index(&object, &index, &bracket)?;
+                        let old = self.check_number_operand(operator.clone(), &value)?;
+                        self.set_index(&object, index, Object::Number(old + delta), &bracket)?;
+                        old
                     }
-                    _ => return Ok(Object::Nil),
+                    // The parser only builds updates of variables and properties.
+                    _ => unreachable!(),
+                };
+                Ok(Object::Number(if prefix { old + delta } else { old }))
+            }
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    fn visit_this_expr(&self, expr: Expr) -> Result<Object, RuntimeError> {
+        match expr {
+            Expr::This { keyword, depth } => self.look_up_variable(keyword, depth),
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    fn visit_super_expr(&self, expr: Expr) -> Result<Object, RuntimeError> {
+        match expr {
+            Expr::Super {
+                keyword,
+                method,
+                depth: Some(distance),
+            } => {
+                let environment = self.environment.borrow();
+                let superclass = environment.get_at(distance, keyword.clone())?;
+                // `this` always lives in the scope just inside `super`.
+                let this = Token {
+                    token_type: TokenType::This,
+                    lexeme: "this".to_string(),
+                    span: keyword.span,
+                };
+                let object = environment.get_at(distance - 1, this)?;
+                let found = match &superclass {
+                    Object::Class(class) => class.find_method(&method.lexeme),
+                    _ => None,
+                };
+                match found {
+                    Some(function) => Ok(Object::Call(Rc::new(function.bind(object)))),
+                    None => Err(RuntimeError::new(
+                        method.span,
+                        format!("Undefined property '{}'.", method.lexeme),
+                    )),
                 }
             }
-            _ => return Ok(Object::Nil),
+            _ => Ok(Object::Nil),
         }
     }
 
-    fn check_number_operand(&self, _operator: Token, operand: &Object) {
+    fn check_number_operand(&self, operator: Token, operand: &Object) -> Result<f64, RuntimeError> {
         match operand {
-            Object::Number(_) => (),
-            _ => println!("Error!"),
+            Object::Number(number) => Ok(*number),
+            _ => Err(RuntimeError::new(
+                operator.span,
+                "Operand must be a number.".to_string(),
+            )),
         }
     }
 
-    //Not the best.
-    fn check_number_operands(&self, operator: Token, left: &Object, right: &Object) {
+    fn check_number_operands(
+        &self,
+        operator: Token,
+        left: &Object,
+        right: &Object,
+    ) -> Result<(f64, f64), RuntimeError> {
         match (left, right) {
-            (Object::Number(_), Object::Number(_)) => (),
-            _ => println!("Error! {:?}", operator),
+            (Object::Number(left), Object::Number(right)) => Ok((*left, *right)),
+            _ => Err(RuntimeError::new(
+                operator.span,
+                "Operands must be numbers.".to_string(),
+            )),
         }
     }
 
-    fn visit_unary_expr(&self, expr: Expr) -> Object {
+    fn visit_unary_expr(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
         match expr {
-            Expr::Unary { operator, right } => match operator.token_type {
-                TokenType::Bang => {
-                    Object::Boolean(!self.is_truthy(self.visit_literal_expr(*right)))
-                }
-                TokenType::Minus => {
-                    let object = self.visit_literal_expr(*right);
-                    self.check_number_operand(operator, &object);
-                    Object::Number(-self.object_number(object))
+            Expr::Unary { operator, right } => {
+                let object = self.interpret(*right)?;
+                match operator.token_type {
+                    TokenType::Bang => Ok(Object::Boolean(!self.is_truthy(object))),
+                    TokenType::Minus => Ok(Object::Number(
+                        -self.check_number_operand(operator, &object)?,
+                    )),
+                    // Wraps each expression embedded in an interpolated string.
+                    TokenType::Interpolation { .. } => Ok(Object::String(object.stringify())),
+                    _ => Ok(Object::Nil),
                 }
-                _ => Object::Nil,
-            },
-            _ => Object::Nil,
+            }
+            _ => Ok(Object::Nil),
         }
     }
 
-    fn visit_expression_stmt(&mut self, stmt: Stmt) {
-        let _object: Option<Result<Object, RuntimeError>> = match stmt {
-            Stmt::Expression { expr } => Some(self.interpret(expr)),
-            _ => None,
-        };
+    fn visit_expression_stmt(&mut self, stmt: Stmt) -> Result<(), RuntimeError> {
+        if let Stmt::Expression { expr, .. } = stmt {
+            self.interpret(expr)?;
+        }
+        Ok(())
+    }
+
+    // Anonymous functions close over the environment they're created in,
+    // exactly like declared ones.
+    fn visit_lambda_expr(&mut self, expr: Expr) -> Object {
+        match expr {
+            Expr::Lambda {
+                keyword,
+                params,
+                body,
+            } => Object::Call(Rc::new(LoxFunc::Function {
+                name: Token {
+                    token_type: TokenType::Identifier,
+                    lexeme: "lambda".to_string(),
+                    span: keyword.span,
+                },
+                params: params.into(),
+                body: body.into(),
+                closure: Rc::clone(&self.environment),
+                is_initializer: false,
+            })),
+            _ => Object::Nil,
+        }
     }
 
     fn visit_function_stmt(&mut self, stmt: Stmt) {
@@ -317,63 +759,184 @@ impl Interpreter {
             Stmt::Function { name, params, body } => {
                 let function = LoxFunc::Function {
                     name: name.clone(),
-                    params,
-                    body,
+                    params: params.into(),
+                    body: body.into(),
                     closure: Rc::clone(&self.environment),
+                    is_initializer: false,
                 };
 
                 self.environment
                     .borrow_mut()
-                    .define(name.lexeme, Object::Call(function));
+                    .define(name.lexeme, Object::Call(Rc::new(function)));
             }
             _ => println!("No function"),
         }
     }
 
-    fn visit_print_stmt(&mut self, stmt: Stmt) {
-        match stmt {
-            Stmt::Print { expr } => {
-                println!("Printing: {:?}", self.interpret(expr).unwrap_or_default())
+    fn visit_class_stmt(&mut self, stmt: Stmt) -> Result<(), RuntimeError> {
+        if let Stmt::Class {
+            name,
+            superclass,
+            methods,
+        } = stmt
+        {
+            let superclass = match superclass {
+                Some(expr) => {
+                    let span = match &expr {
+                        Expr::Variable { token, .. } => token.span,
+                        _ => name.span,
+                    };
+                    match self.interpret(expr)? {
+                        Object::Class(class) => Some(class),
+                        _ => {
+                            return Err(RuntimeError::new(
+                                span,
+                                "Superclass must be a class.".to_string(),
+                            ))
+                        }
+                    }
+                }
+                None => None,
+            };
+
+            // Methods of a subclass close over an environment holding `super`,
+            // mirroring the extra scope the resolver opened.
+            let enclosing = Rc::clone(&self.environment);
+            if let Some(superclass) = &superclass {
+                let mut environment = Environment::new_with_enclosing(&self.environment);
+                environment.define("super".to_string(), Object::Class(Rc::clone(superclass)));
+                self.environment = Rc::new(RefCell::new(environment));
             }
+
+            let mut class_methods = HashMap::new();
+            for method in methods {
+                if let Stmt::Function { name, params, body } = method {
+                    let function = LoxFunc::Function {
+                        is_initializer: name.lexeme == "init",
+                        name: name.clone(),
+                        params: params.into(),
+                        body: body.into(),
+                        closure: Rc::clone(&self.environment),
+                    };
+                    class_methods.insert(name.lexeme, function);
+                }
+            }
+            let class = LoxClass {
+                name: name.lexeme.clone(),
+                superclass,
+                methods: class_methods,
+            };
+            self.environment = enclosing;
+            self.environment
+                .borrow_mut()
+                .define(name.lexeme, Object::Class(Rc::new(class)));
+        }
+        Ok(())
+    }
+
+    fn visit_print_stmt(&mut self, stmt: Stmt) -> Result<(), RuntimeError> {
+        match stmt {
+            Stmt::Print { expr, .. } => println!("{}", self.interpret(expr)?.stringify()),
             _ => println!("None"),
         };
+        Ok(())
     }
 
     fn visit_return_stmt(&mut self, stmt: Stmt) -> Result<(), Error> {
         match stmt {
-            Stmt::Return { value, .. } => {
-                match value {
-                    Some(expr) => {
-                        return Err(Error::ReturnError {
-                            value: self.interpret(expr).unwrap(),
-                        });
-                    }
-                    None => return Ok(()),
+            Stmt::Return { keyword, value } => {
+                let value = match value {
+                    Some(expr) => self.interpret(expr)?,
+                    None => Object::Nil,
                 };
+                Err(Error::ReturnError {
+                    value,
+                    span: keyword.span,
+                })
             }
-            _ => return Ok(()),
+            _ => Ok(()),
         }
     }
 
-    fn visit_var_stmt(&mut self, stmt: Stmt) {
+    fn visit_var_stmt(&mut self, stmt: Stmt) -> Result<(), RuntimeError> {
         match stmt {
             Stmt::Var { name, initializer } => {
                 let value = match initializer {
-                    Some(initializer) => self.interpret(initializer),
-                    None => Ok(Object::Nil),
+                    Some(initializer) => self.interpret(initializer)?,
+                    None => Object::Nil,
                 };
-                match value {
-                    Err(e) => panic!("{:?}", e),
-                    Ok(value) => self.environment.borrow_mut().define(name.lexeme, value),
-                }
+                self.environment.borrow_mut().define(name.lexeme, value);
             }
             _ => println!("None"),
         };
+        Ok(())
+    }
+
+    fn visit_throw_stmt(&mut self, stmt: Stmt) -> Result<(), Error> {
+        match stmt {
+            Stmt::Throw { keyword, value } => {
+                let value = self.interpret(value)?;
+                Err(RuntimeError::thrown(keyword, value).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Only errors are caught, a `return` passes straight through. `finally`
+    // runs either way, and an error or return inside it wins over whatever
+    // was already on its way out.
+    fn visit_try_stmt(&mut self, stmt: Stmt) -> Result<(), Error> {
+        match stmt {
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                let mut result = self.execute_block(body, self.new_scope());
+                if let Some((name, handler)) = catch {
+                    // `exit` unwinds like an error, but can't be caught.
+                    result = match result {
+                        Err(Error::RuntimeError(error)) if error.exit.is_none() => {
+                            let environment = self.new_scope();
+                            environment
+                                .borrow_mut()
+                                .define(name.lexeme, self.error_value(error));
+                            self.execute_block(handler, environment)
+                        }
+                        result => result,
+                    };
+                }
+                if let Some(finally) = finally {
+                    self.execute_block(finally, self.new_scope())?;
+                }
+                result
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // The value a `catch` clause sees: whatever was thrown, or an `Error`
+    // instance with `message` and `line` for the interpreter's own errors.
+    fn error_value(&self, error: RuntimeError) -> Object {
+        if let Some(value) = error.value {
+            return Rc::unwrap_or_clone(value);
+        }
+        let mut instance = LoxInstance::new(&self.error_class);
+        instance.set("message", Object::String(error.message));
+        instance.set("line", Object::Number(error.span.line as f64));
+        Object::Instance(Rc::new(RefCell::new(instance)))
+    }
+
+    fn new_scope(&self) -> Rc<RefCell<Environment>> {
+        Rc::new(RefCell::new(Environment::new_with_enclosing(
+            &self.environment,
+        )))
     }
 
     fn visit_block_stmt(&mut self, stmt: Stmt) -> Result<(), Error> {
         match stmt {
-            Stmt::Block { statements } => self.execute_block(
+            Stmt::Block { statements, .. } => self.execute_block(
                 statements,
                 Rc::new(RefCell::new(Environment::new_with_enclosing(
                     &self.environment,
@@ -385,12 +948,25 @@ impl Interpreter {
 
     fn visit_while_stmt(&mut self, stmt: Stmt) -> Result<(), Error> {
         match stmt {
-            Stmt::WhileStmt { condition, body } => {
-                let condition = condition.clone();
-                let mut value = self.interpret(condition.clone()).unwrap();
-                while self.is_truthy_2(&value) {
-                    self.interpret_stmt(*body.clone())?;
-                    value = self.interpret(condition.clone())?;
+            Stmt::WhileStmt {
+                condition,
+                body,
+                increment,
+                ..
+            } => {
+                loop {
+                    let value = self.interpret(condition.clone())?;
+                    if !self.is_truthy_2(&value) {
+                        break;
+                    }
+                    match self.interpret_stmt(*body.clone()) {
+                        Ok(()) | Err(Error::Continue { .. }) => (),
+                        Err(Error::Break { .. }) => break,
+                        Err(error) => return Err(error),
+                    }
+                    if let Some(increment) = &increment {
+                        self.interpret(increment.clone())?;
+                    }
                 }
                 Ok(())
             }
@@ -423,49 +999,68 @@ impl Interpreter {
 
     fn visit_var_expr(&self, expr: Expr) -> Result<Object, RuntimeError> {
         match expr {
-            Expr::Variable { token } => return self.environment.borrow().get(token),
+            Expr::Variable { token, depth } => self.look_up_variable(token, depth),
             _ => panic!("Not here! Error"),
         }
     }
 
+    fn look_up_variable(&self, name: Token, depth: Option<usize>) -> Result<Object, RuntimeError> {
+        match depth {
+            Some(distance) => self.environment.borrow().get_at(distance, name),
+            None => self.globals.borrow().get(name),
+        }
+    }
+
     fn visit_assign_expr(&mut self, expr: Expr) -> Result<Object, RuntimeError> {
         match expr {
-            Expr::Assign { name, value } => {
-                let new_value = self.interpret(*value);
-                match new_value {
-                    Ok(obj) => {
-                        self.environment.borrow_mut().assign(name, obj.clone())?;
-                        return Ok(obj);
-                    }
-                    Err(e) => return Err(e),
-                }
+            Expr::Assign { name, value, depth } => {
+                let new_value = self.interpret(*value)?;
+                self.assign_variable(name, depth, new_value.clone())?;
+                Ok(new_value)
             }
             _ => panic!("Not here! error visiting assign expression"),
         }
     }
 
+    fn assign_variable(
+        &mut self,
+        name: Token,
+        depth: Option<usize>,
+        value: Object,
+    ) -> Result<(), RuntimeError> {
+        match depth {
+            Some(distance) => self
+                .environment
+                .borrow_mut()
+                .assign_at(distance, name, value),
+            None => self.globals.borrow_mut().assign(name, value),
+        }
+    }
+
     fn visit_if_stmt(&mut self, stmt: Stmt) -> Result<(), Error> {
         match stmt {
             Stmt::IfStmt {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => match self.interpret(condition) {
                 Ok(obj) => {
                     if self.is_truthy(obj) {
                         return self.interpret_stmt(*then_branch);
                     }
-                    if else_branch.is_some() {
-                        return self.interpret_stmt(*else_branch.unwrap());
+                    if let Some(else_branch) = else_branch {
+                        return self.interpret_stmt(*else_branch);
                     }
                     Ok(())
                 }
-                Err(e) => {
-                    println!("Error: {:?}", e);
-                    Err(e.into())
-                }
+                Err(e) => Err(e.into()),
             },
             _ => Ok(()),
         }
     }
 }
+
+fn arity_message(arity: Arity, count: usize) -> String {
+    format!("Expected {} arguments but got {}.", arity, count)
+}
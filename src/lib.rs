@@ -0,0 +1,154 @@
+use std::fs;
+use std::path::Path;
+
+pub mod callable;
+pub mod class;
+pub mod diagnostic;
+pub mod environment;
+pub mod error;
+pub mod expr;
+pub mod interpreter;
+pub mod list;
+pub mod map;
+pub mod object;
+pub mod parser;
+pub mod prelude;
+pub mod resolver;
+pub mod scanner;
+pub mod token;
+
+pub use crate::callable::{Arity, LoxFunc};
+pub use crate::diagnostic::{Diagnostic, Renderer, SourceMap};
+pub use crate::error::{LoxError, RuntimeError};
+pub use crate::interpreter::Interpreter;
+pub use crate::object::Object;
+
+use crate::expr::Stmt;
+use crate::token::{Span, Token, TokenType};
+
+// Embedding API. One `Lox` keeps its globals between calls, so scripts can be
+// loaded once and their functions called from Rust afterwards.
+pub struct Lox {
+    pub interpreter: Interpreter,
+    sources: SourceMap,
+    unicode_identifiers: bool,
+}
+
+impl Default for Lox {
+    fn default() -> Self {
+        Lox::new()
+    }
+}
+
+impl Lox {
+    pub fn new() -> Lox {
+        Lox {
+            interpreter: Interpreter::new(),
+            sources: SourceMap::new(),
+            unicode_identifiers: false,
+        }
+    }
+
+    // Accept identifiers written in any script in everything run from now on.
+    pub fn set_unicode_identifiers(&mut self, enabled: bool) {
+        self.unicode_identifiers = enabled;
+    }
+
+    // Runs `source` and returns the value of its final expression statement,
+    // or nil if it doesn't end in one.
+    pub fn eval(&mut self, source: &str) -> Result<Object, LoxError> {
+        self.eval_named("<eval>", source)
+    }
+
+    // Same as `eval`, but diagnostics point at `name` instead of `<eval>`.
+    pub fn eval_named(&mut self, name: &str, source: &str) -> Result<Object, LoxError> {
+        let file_id = self.sources.add(name.to_string(), source.to_string());
+        let mut scanner = scanner::Scanner::new(source.to_string(), file_id)
+            .with_unicode_identifiers(self.unicode_identifiers);
+        let (tokens, errors) = scanner.scan_tokens();
+        if !errors.is_empty() {
+            return Err(LoxError::Scan(errors));
+        }
+        let mut parser = parser::Parser::new(tokens);
+        let mut statements = parser.parse().map_err(LoxError::Parse)?;
+        let mut resolver = resolver::Resolver::new();
+        resolver
+            .resolve(&mut statements)
+            .map_err(LoxError::Resolve)?;
+
+        let last = match statements.last() {
+            Some(Stmt::Expression { .. }) => statements.pop(),
+            _ => None,
+        };
+        self.interpreter.interpret_stmts(statements)?;
+        match last {
+            Some(Stmt::Expression { expr, .. }) => Ok(self.interpreter.interpret(expr)?),
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    pub fn run_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Object, LoxError> {
+        let contents = fs::read_to_string(&path)?;
+        self.eval_named(&path.as_ref().display().to_string(), &contents)
+    }
+
+    // Runs a file of Lox definitions before any script, e.g. shared helper
+    // functions. Its globals stay visible to everything run afterwards.
+    pub fn load_prelude<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoxError> {
+        self.run_file(path).map(|_| ())
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Object) {
+        self.interpreter
+            .globals
+            .borrow_mut()
+            .define(name.to_string(), value);
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Object> {
+        self.interpreter.globals.borrow().get_local(name)
+    }
+
+    // Calls a global function or class by name, as if a script had written
+    // `name(args...)`.
+    pub fn call(&mut self, name: &str, args: Vec<Object>) -> Result<Object, LoxError> {
+        let token = Token {
+            token_type: TokenType::Identifier,
+            lexeme: name.to_string(),
+            span: Span::default(),
+        };
+        let callee = self.interpreter.globals.borrow().get(token.clone())?;
+        Ok(self.interpreter.call_value(callee, args, &token)?)
+    }
+
+    // Registers a Rust closure as a global function scripts can call.
+    pub fn define_native<F>(&mut self, name: &str, arity: Arity, func: F)
+    where
+        F: Fn(&mut Interpreter, Vec<Object>) -> Result<Object, RuntimeError> + 'static,
+    {
+        self.interpreter.define_native(name, arity, func);
+    }
+
+    // Source text of everything run so far, needed to render diagnostics.
+    pub fn sources(&self) -> &SourceMap {
+        &self.sources
+    }
+
+    // Runtime errors raised inside a function are preceded by a traceback.
+    pub fn render(&self, error: &LoxError, renderer: &Renderer) -> String {
+        let diagnostics = error.diagnostics();
+        if diagnostics.is_empty() {
+            return format!("error: {}\n", error);
+        }
+        let traceback = match error {
+            LoxError::Runtime(error) => renderer.render_backtrace(error, &self.sources),
+            _ => String::new(),
+        };
+        traceback
+            + &diagnostics
+                .iter()
+                .map(|diagnostic| renderer.render(diagnostic, &self.sources))
+                .collect::<Vec<String>>()
+                .join("\n")
+    }
+}
@@ -0,0 +1,122 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::callable::{Arity, LoxFunc};
+use crate::error::RuntimeError;
+use crate::object::Object;
+use crate::token::{Span, Token};
+
+// Lists have reference semantics: `var b = a;` gives two names for one list.
+pub type List = Rc<RefCell<Vec<Object>>>;
+
+pub fn new(elements: Vec<Object>) -> Object {
+    Object::List(Rc::new(RefCell::new(elements)))
+}
+
+// Checks that `index` can be used to look up an element of a list of length
+// `len`. Errors point at `span`, the `[` for index expressions.
+pub fn index(index: &Object, len: usize, span: Span) -> Result<usize, RuntimeError> {
+    let position = whole_number(index, span)?;
+    if position < 0.0 || position >= len as f64 {
+        return Err(RuntimeError::new(
+            span,
+            format!(
+                "Index {} is out of bounds for a list of length {}.",
+                position, len
+            ),
+        ));
+    }
+    Ok(position as usize)
+}
+
+// `xs[start:end]` copies the elements from `start` up to, but not including,
+// `end`. Either bound can be left out.
+pub fn slice(
+    list: &List,
+    start: Option<Object>,
+    end: Option<Object>,
+    span: Span,
+) -> Result<Object, RuntimeError> {
+    let elements = list.borrow();
+    let len = elements.len();
+    let start = match start {
+        Some(start) => whole_number(&start, span)?,
+        None => 0.0,
+    };
+    let end = match end {
+        Some(end) => whole_number(&end, span)?,
+        None => len as f64,
+    };
+    if start < 0.0 || start > end || end > len as f64 {
+        return Err(RuntimeError::new(
+            span,
+            format!(
+                "Slice [{}:{}] is out of bounds for a list of length {}.",
+                start, end, len
+            ),
+        ));
+    }
+    Ok(new(elements[start as usize..end as usize].to_vec()))
+}
+
+pub fn concatenate(left: &List, right: &List) -> Object {
+    let mut elements = left.borrow().clone();
+    elements.extend(right.borrow().iter().cloned());
+    new(elements)
+}
+
+// `xs.push` and friends, as natives bound to the list.
+pub fn method(list: &List, name: &Token) -> Result<Object, RuntimeError> {
+    let list = Rc::clone(list);
+    let method = match name.lexeme.as_str() {
+        "push" => LoxFunc::native("push", Arity::Fixed(1), move |_, mut args| {
+            list.borrow_mut().push(args.remove(0));
+            Ok(Object::Nil)
+        }),
+        "pop" => LoxFunc::native("pop", Arity::Fixed(0), move |_, _| {
+            list.borrow_mut()
+                .pop()
+                .ok_or_else(|| RuntimeError::native("Can't pop from an empty list.".to_string()))
+        }),
+        "len" => LoxFunc::native("len", Arity::Fixed(0), move |_, _| {
+            Ok(Object::Number(list.borrow().len() as f64))
+        }),
+        "insert" => LoxFunc::native("insert", Arity::Fixed(2), move |_, mut args| {
+            let len = list.borrow().len();
+            let position = whole_number(&args[0], Span::default())?;
+            if position < 0.0 || position > len as f64 {
+                return Err(RuntimeError::native(format!(
+                    "Can't insert at index {} into a list of length {}.",
+                    position, len
+                )));
+            }
+            list.borrow_mut().insert(position as usize, args.remove(1));
+            Ok(Object::Nil)
+        }),
+        "remove" => LoxFunc::native("remove", Arity::Fixed(1), move |_, args| {
+            let position = index(&args[0], list.borrow().len(), Span::default())?;
+            Ok(list.borrow_mut().remove(position))
+        }),
+        _ => {
+            return Err(RuntimeError::new(
+                name.span,
+                format!("Lists have no method '{}'.", name.lexeme),
+            ))
+        }
+    };
+    Ok(Object::Call(Rc::new(method)))
+}
+
+fn whole_number(index: &Object, span: Span) -> Result<f64, RuntimeError> {
+    match index {
+        Object::Number(number) if number.fract() == 0.0 => Ok(*number),
+        Object::Number(number) => Err(RuntimeError::new(
+            span,
+            format!("List index must be a whole number, not {}.", number),
+        )),
+        other => Err(RuntimeError::new(
+            span,
+            format!("List index must be a number, not a {}.", other.type_name()),
+        )),
+    }
+}
@@ -1,67 +1,111 @@
-use std::fs;
-use std::io;
+use std::io::{self, IsTerminal, Write};
+use std::process;
 
-mod callable;
-mod environment;
-mod error;
-mod expr;
-mod interpreter;
-mod object;
-mod parser;
-mod scanner;
-mod token;
+use lox::{Lox, LoxError, Object, Renderer};
 
 fn main() {
-    let mut pattern = std::env::args();
-    let interpreter = interpreter::Interpreter::new();
-    let mut lox = Lox { interpreter };
+    let mut args = std::env::args().skip(1).peekable();
+    // Colour only when a person is looking at the terminal.
+    let renderer = if io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none() {
+        Renderer::colored()
+    } else {
+        Renderer::plain()
+    };
+    let mut cli = Cli {
+        lox: Lox::new(),
+        renderer,
+    };
 
-    match pattern.len() {
-        1 => lox.run_prompt(),
-        2 => lox.run_file(pattern.nth(1).clone().expect("error")),
-        _ => println!(
-            "Invalid arguments. Run without arguments for REPL or pass a path to a .lox file"
-        ),
+    // `--prelude <file>` runs a Lox file of definitions before the script.
+    let mut prelude = None;
+    while let Some(option) = args.next_if(|arg| arg.starts_with("--")) {
+        match option.as_str() {
+            "--prelude" => prelude = Some(args.next().unwrap_or_else(|| usage())),
+            "--unicode-identifiers" => cli.lox.set_unicode_identifiers(true),
+            _ => usage(),
+        }
+    }
+    if let Some(prelude) = prelude {
+        cli.load_prelude(&prelude);
+    }
+
+    match (args.next(), args.next()) {
+        (None, _) => cli.run_prompt(),
+        (Some(script), None) => cli.run_file(script),
+        _ => usage(),
     }
 }
-struct Lox {
-    pub interpreter: interpreter::Interpreter,
+
+fn usage() -> ! {
+    println!("Usage: lox [--prelude <file>] [--unicode-identifiers] [script]");
+    process::exit(64);
+}
+
+// `print` without a newline may still be buffered, and `process::exit`
+// doesn't flush it.
+fn exit(code: i32) -> ! {
+    let _ = io::stdout().flush();
+    process::exit(code);
 }
 
-impl Lox {
-    pub fn run_file(&mut self, path: String) {
-        let contents = fs::read_to_string(path).expect("no file found");
+struct Cli {
+    lox: Lox,
+    renderer: Renderer,
+}
 
-        self.run(contents);
-        //if (HAD_ERROR) {
-        //    process::exit(1);
-        //}
+impl Cli {
+    fn run_file(&mut self, path: String) {
+        if let Err(error) = self.lox.run_file(&path) {
+            self.fail(&error);
+        }
+    }
+
+    fn load_prelude(&mut self, path: &str) {
+        if let Err(error) = self.lox.load_prelude(path) {
+            self.fail(&error);
+        }
     }
 
-    pub fn run_prompt(&mut self) {
+    fn fail(&self, error: &LoxError) -> ! {
+        // Exit codes follow the reference implementation (sysexits.h).
+        let code = match error {
+            LoxError::Io(_) => 66,
+            LoxError::Scan(_) | LoxError::Parse(_) | LoxError::Resolve(_) => 65,
+            LoxError::Runtime(_) => 70,
+            // The script asked to stop, there's nothing to report.
+            LoxError::Exit(code) => exit(*code),
+        };
+        self.report(error);
+        process::exit(code);
+    }
+
+    fn run_prompt(&mut self) {
         println!("prompt");
         let stdin = io::stdin();
         let mut user_input = String::new();
 
         loop {
             print!(">");
+            io::stdout().flush().expect("Error writing prompt");
             stdin
                 .read_line(&mut user_input)
                 .expect("Error reading input");
-            if user_input == "" {
+            if user_input.is_empty() {
                 break;
             }
-            self.run(user_input.clone());
-            //HAD_ERROR = false;
+            // A trailing expression statement's value is echoed, unless
+            // it's nil, which is also what every other statement gives.
+            match self.lox.eval_named("<repl>", &user_input) {
+                Ok(Object::Nil) => (),
+                Ok(value) => println!("{}", value.repr()),
+                Err(LoxError::Exit(code)) => exit(code),
+                Err(error) => self.report(&error),
+            }
             user_input = "".to_string();
         }
     }
 
-    fn run(&mut self, source: String) {
-        let mut scanner = scanner::Scanner::new(source);
-        let tokens = scanner.scan_tokens();
-        let mut parser = parser::Parser::new(tokens);
-        let statements = parser.parse();
-        self.interpreter.interpret_stmts(statements);
+    fn report(&self, error: &LoxError) {
+        eprintln!("{}", self.lox.render(error, &self.renderer));
     }
 }
@@ -0,0 +1,181 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::callable::{Arity, LoxFunc};
+use crate::error::RuntimeError;
+use crate::list;
+use crate::object::Object;
+use crate::token::{Span, Token};
+
+// Maps have reference semantics, like lists.
+pub type Map = Rc<RefCell<LoxMap>>;
+
+// A value that can be a map key: a number, string, boolean or nil. Hashing
+// and equality are `Object`'s own. NaN equals nothing, not even itself, so
+// it's rejected, which makes the equality a real `Eq`.
+#[derive(Debug, Clone, PartialEq, Hash)]
+struct Key(Object);
+
+impl Eq for Key {}
+
+impl Key {
+    fn new(value: &Object, span: Span) -> Result<Key, RuntimeError> {
+        match value {
+            Object::Number(number) if number.is_nan() => Err(RuntimeError::new(
+                span,
+                "NaN can't be a map key.".to_string(),
+            )),
+            Object::Number(_) | Object::String(_) | Object::Boolean(_) | Object::Nil => {
+                Ok(Key(value.clone()))
+            }
+            other => Err(RuntimeError::new(
+                span,
+                format!(
+                    "Only numbers, strings, booleans and nil can be map keys, not a {}.",
+                    other.type_name()
+                ),
+            )),
+        }
+    }
+}
+
+// Entries are kept in insertion order, so iterating over a map is
+// deterministic.
+#[derive(Debug, Clone, Default)]
+pub struct LoxMap {
+    indices: HashMap<Key, usize>,
+    entries: Vec<(Object, Object)>,
+}
+
+impl LoxMap {
+    pub fn get(&self, key: &Object, span: Span) -> Result<Option<Object>, RuntimeError> {
+        let key = Key::new(key, span)?;
+        Ok(self
+            .indices
+            .get(&key)
+            .map(|&index| self.entries[index].1.clone()))
+    }
+
+    pub fn insert(&mut self, key: Object, value: Object, span: Span) -> Result<(), RuntimeError> {
+        let hashed = Key::new(&key, span)?;
+        match self.indices.get(&hashed) {
+            Some(&index) => self.entries[index].1 = value,
+            None => {
+                self.indices.insert(hashed, self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key: &Object, span: Span) -> Result<Option<Object>, RuntimeError> {
+        let Some(index) = self.indices.remove(&Key::new(key, span)?) else {
+            return Ok(None);
+        };
+        let (_, value) = self.entries.remove(index);
+        for later in self.indices.values_mut() {
+            if *later > index {
+                *later -= 1;
+            }
+        }
+        Ok(Some(value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[(Object, Object)] {
+        &self.entries
+    }
+}
+
+// Maps are equal when they have the same keys with equal values, in any
+// order.
+impl PartialEq for LoxMap {
+    fn eq(&self, other: &LoxMap) -> bool {
+        self.equals(other, &mut Vec::new())
+    }
+}
+
+impl LoxMap {
+    // See `Object::equals`.
+    pub(crate) fn equals(
+        &self,
+        other: &LoxMap,
+        comparing: &mut Vec<(*const (), *const ())>,
+    ) -> bool {
+        self.len() == other.len()
+            && self.entries.iter().all(|(key, value)| {
+                other
+                    .indices
+                    .get(&Key(key.clone()))
+                    .is_some_and(|&index| value.equals(&other.entries[index].1, comparing))
+            })
+    }
+}
+
+pub fn new(map: LoxMap) -> Object {
+    Object::Map(Rc::new(RefCell::new(map)))
+}
+
+// `m[key]`. A missing key is an error, `has` checks first.
+pub fn get(map: &Map, key: &Object, span: Span) -> Result<Object, RuntimeError> {
+    match map.borrow().get(key, span)? {
+        Some(value) => Ok(value),
+        None => Err(RuntimeError::new(
+            span,
+            format!("Key {} not found.", key.repr()),
+        )),
+    }
+}
+
+// `m.keys` and friends, as natives bound to the map.
+pub fn method(map: &Map, name: &Token) -> Result<Object, RuntimeError> {
+    let map = Rc::clone(map);
+    let method = match name.lexeme.as_str() {
+        "keys" => LoxFunc::native("keys", Arity::Fixed(0), move |_, _| {
+            let map = map.borrow();
+            Ok(list::new(
+                map.entries().iter().map(|(key, _)| key.clone()).collect(),
+            ))
+        }),
+        "values" => LoxFunc::native("values", Arity::Fixed(0), move |_, _| {
+            let map = map.borrow();
+            Ok(list::new(
+                map.entries()
+                    .iter()
+                    .map(|(_, value)| value.clone())
+                    .collect(),
+            ))
+        }),
+        "has" => LoxFunc::native("has", Arity::Fixed(1), move |_, args| {
+            let found = map.borrow().get(&args[0], Span::default())?.is_some();
+            Ok(Object::Boolean(found))
+        }),
+        "remove" => LoxFunc::native("remove", Arity::Fixed(1), move |_, args| {
+            match map.borrow_mut().remove(&args[0], Span::default())? {
+                Some(value) => Ok(value),
+                None => Err(RuntimeError::native(format!(
+                    "Key {} not found.",
+                    args[0].repr()
+                ))),
+            }
+        }),
+        "len" => LoxFunc::native("len", Arity::Fixed(0), move |_, _| {
+            Ok(Object::Number(map.borrow().len() as f64))
+        }),
+        _ => {
+            return Err(RuntimeError::new(
+                name.span,
+                format!("Maps have no method '{}'.", name.lexeme),
+            ))
+        }
+    };
+    Ok(Object::Call(Rc::new(method)))
+}
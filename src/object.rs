@@ -1,29 +1,492 @@
 use crate::callable::LoxFunc;
+use crate::class::{LoxClass, LoxInstance};
+use crate::list::List;
+use crate::map::Map;
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum Object {
     Number(f64),
     String(String),
+    #[default]
     Nil,
     Boolean(bool),
-    Call(LoxFunc),
+    Call(Rc<LoxFunc>),
+    Class(Rc<LoxClass>),
+    Instance(Rc<RefCell<LoxInstance>>),
+    List(List),
+    Map(Map),
 }
 
-impl Default for Object {
-    fn default() -> Self {
-        Object::Nil
+impl Object {
+    // Only nil and false are falsey.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Object::Nil | Object::Boolean(false))
+    }
+
+    // What `print`, `str()`, string concatenation and interpolation turn a
+    // value into. Same as `Display`.
+    pub fn stringify(&self) -> String {
+        self.to_string()
+    }
+
+    // Like `stringify`, but strings are quoted and escaped so they can be
+    // told apart from other values. The REPL echoes results this way.
+    pub fn repr(&self) -> String {
+        let mut text = String::new();
+        let _ = self.write(&mut text, true, &mut Vec::new());
+        text
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Number(_) => "number",
+            Object::String(_) => "string",
+            Object::Nil => "nil",
+            Object::Boolean(_) => "boolean",
+            Object::Call(_) => "function",
+            Object::Class(_) => "class",
+            Object::Instance(_) => "instance",
+            Object::List(_) => "list",
+            Object::Map(_) => "map",
+        }
+    }
+}
+
+// Numbers, strings, booleans and nil compare by value, lists and maps by
+// their contents. Functions, classes and instances are only equal to
+// themselves. NaN is equal to nothing, as in IEEE 754, and -0 equals 0.
+impl PartialEq for Object {
+    fn eq(&self, other: &Object) -> bool {
+        self.equals(other, &mut Vec::new())
+    }
+}
+
+impl Object {
+    // `comparing` holds the pairs of lists and maps being compared. Meeting
+    // one again means the two collections contain themselves in the same
+    // place, and nothing found so far tells them apart, so the pair counts as
+    // equal instead of being compared forever.
+    pub(crate) fn equals(
+        &self,
+        other: &Object,
+        comparing: &mut Vec<(*const (), *const ())>,
+    ) -> bool {
+        match (self, other) {
+            (Object::Number(a), Object::Number(b)) => a == b,
+            (Object::String(a), Object::String(b)) => a == b,
+            (Object::Boolean(a), Object::Boolean(b)) => a == b,
+            (Object::Nil, Object::Nil) => true,
+            (Object::Call(a), Object::Call(b)) => Rc::ptr_eq(a, b),
+            (Object::Class(a), Object::Class(b)) => Rc::ptr_eq(a, b),
+            (Object::Instance(a), Object::Instance(b)) => Rc::ptr_eq(a, b),
+            (Object::List(a), Object::List(b)) => {
+                if Rc::ptr_eq(a, b) {
+                    return true;
+                }
+                let pair = (Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ());
+                if comparing.contains(&pair) {
+                    return true;
+                }
+                comparing.push(pair);
+                let (a, b) = (a.borrow(), b.borrow());
+                let equal = a.len() == b.len()
+                    && a.iter().zip(b.iter()).all(|(a, b)| a.equals(b, comparing));
+                comparing.pop();
+                equal
+            }
+            (Object::Map(a), Object::Map(b)) => {
+                if Rc::ptr_eq(a, b) {
+                    return true;
+                }
+                let pair = (Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ());
+                if comparing.contains(&pair) {
+                    return true;
+                }
+                comparing.push(pair);
+                let equal = a.borrow().equals(&b.borrow(), comparing);
+                comparing.pop();
+                equal
+            }
+            _ => false,
+        }
+    }
+}
+
+// Agrees with `eq`: values that are equal hash alike. Lists and maps only
+// hash their length, which keeps it cheap and finite for a list that
+// contains itself.
+impl Hash for Object {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Object::Number(number) => {
+                let number = if *number == 0.0 { 0.0 } else { *number };
+                number.to_bits().hash(state)
+            }
+            Object::String(text) => text.hash(state),
+            Object::Boolean(value) => value.hash(state),
+            Object::Nil => (),
+            Object::Call(function) => Rc::as_ptr(function).hash(state),
+            Object::Class(class) => Rc::as_ptr(class).hash(state),
+            Object::Instance(instance) => Rc::as_ptr(instance).hash(state),
+            Object::List(list) => list.borrow().len().hash(state),
+            Object::Map(map) => map.borrow().len().hash(state),
+        }
     }
 }
 
-impl<'a> fmt::Display for Object {
+// Numbers order numerically and strings lexicographically. Anything else
+// is only ordered against values it's equal to.
+impl PartialOrd for Object {
+    fn partial_cmp(&self, other: &Object) -> Option<Ordering> {
+        match (self, other) {
+            (Object::Number(a), Object::Number(b)) => a.partial_cmp(b),
+            (Object::String(a), Object::String(b)) => Some(a.cmp(b)),
+            _ if self == other => Some(Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+// The canonical text of a value, in the reference jlox format: `1` rather
+// than `1.0`, strings without quotes, `<fn name>`, `<native fn>`, `nil`.
+impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, false, &mut Vec::new())
+    }
+}
+
+impl Object {
+    // Elements of lists and maps are always quoted, so `["1", 1]` doesn't
+    // read as two numbers. `seen` holds the lists and maps being written, a
+    // collection that contains itself prints as `[...]` or `{...}`.
+    fn write(
+        &self,
+        f: &mut dyn fmt::Write,
+        quoted: bool,
+        seen: &mut Vec<*const ()>,
+    ) -> fmt::Result {
         match self {
-            Object::Number(x) => write!(f, "{}", x),
-            Object::String(x) => write!(f, "\"{}\"", x),
-            Object::Boolean(x) => write!(f, "\"{}\"", x),
+            Object::Number(number) if number.is_infinite() => {
+                write!(f, "{}Infinity", if *number < 0.0 { "-" } else { "" })
+            }
+            Object::Number(number) => write_number(f, *number),
+            Object::String(text) if quoted => write_quoted(f, text),
+            Object::String(text) => write!(f, "{}", text),
+            Object::Boolean(value) => write!(f, "{}", value),
             Object::Nil => write!(f, "nil"),
-            _ => write!(f, "Nil"),
+            Object::Call(function) => write!(f, "{}", function),
+            Object::Class(class) => write!(f, "{}", class),
+            Object::Instance(instance) => write!(f, "{}", instance.borrow()),
+            Object::List(list) => {
+                let id = Rc::as_ptr(list) as *const ();
+                if seen.contains(&id) {
+                    return write!(f, "[...]");
+                }
+                seen.push(id);
+                write!(f, "[")?;
+                for (i, element) in list.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    element.write(f, true, seen)?;
+                }
+                seen.pop();
+                write!(f, "]")
+            }
+            Object::Map(map) => {
+                let id = Rc::as_ptr(map) as *const ();
+                if seen.contains(&id) {
+                    return write!(f, "{{...}}");
+                }
+                seen.push(id);
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.borrow().entries().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    key.write(f, true, seen)?;
+                    write!(f, ": ")?;
+                    value.write(f, true, seen)?;
+                }
+                seen.pop();
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+// Like Java's `Double.toString`, which jlox uses: plain decimals from 0.001
+// up to 10^7, scientific notation such as `1.0E-7` outside of that.
+fn write_number(f: &mut dyn fmt::Write, number: f64) -> fmt::Result {
+    let magnitude = number.abs();
+    if number == 0.0 || number.is_nan() || (1e-3..1e7).contains(&magnitude) {
+        return write!(f, "{}", number);
+    }
+    let text = format!("{:e}", number);
+    let (mantissa, exponent) = text
+        .split_once('e')
+        .expect("exponent in scientific notation");
+    if mantissa.contains('.') {
+        write!(f, "{}E{}", mantissa, exponent)
+    } else {
+        write!(f, "{}.0E{}", mantissa, exponent)
+    }
+}
+
+// A string as it would be written in Lox source.
+fn write_quoted(f: &mut dyn fmt::Write, text: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '\r' => write!(f, "\\r")?,
+            '\0' => write!(f, "\\0")?,
+            '$' if chars.peek() == Some(&'{') => write!(f, "\\$")?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::callable::{Arity, LoxFunc};
+    use crate::map::LoxMap;
+    use crate::token::Span;
+    use crate::{list, map};
+
+    fn hash(value: &Object) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn number(value: f64) -> Object {
+        Object::Number(value)
+    }
+
+    fn string(value: &str) -> Object {
+        Object::String(value.to_string())
+    }
+
+    fn native() -> Object {
+        Object::Call(Rc::new(LoxFunc::native("f", Arity::Fixed(0), |_, _| {
+            Ok(Object::Nil)
+        })))
+    }
+
+    fn class(name: &str) -> Rc<LoxClass> {
+        Rc::new(LoxClass {
+            name: name.to_string(),
+            superclass: None,
+            methods: HashMap::new(),
+        })
+    }
+
+    fn instance(class: &Rc<LoxClass>) -> Object {
+        Object::Instance(Rc::new(RefCell::new(LoxInstance::new(class))))
+    }
+
+    fn map_of(entries: Vec<(Object, Object)>) -> Object {
+        let mut map = LoxMap::default();
+        for (key, value) in entries {
+            map.insert(key, value, Span::default()).unwrap();
+        }
+        map::new(map)
+    }
+
+    // One value of every variant, none equal to another.
+    fn samples() -> Vec<Object> {
+        let point = class("Point");
+        vec![
+            number(1.0),
+            string("1"),
+            Object::Nil,
+            Object::Boolean(true),
+            native(),
+            instance(&point),
+            Object::Class(point),
+            list::new(vec![number(1.0)]),
+            map_of(vec![(number(1.0), number(1.0))]),
+        ]
+    }
+
+    #[test]
+    fn every_variant_only_equals_itself() {
+        let samples = samples();
+        let copies = samples.clone();
+        for (i, a) in samples.iter().enumerate() {
+            for (j, b) in copies.iter().enumerate() {
+                let same = i == j;
+                assert_eq!(a == b, same, "{} == {}", a.type_name(), b.type_name());
+                assert_eq!(
+                    a.partial_cmp(b) == Some(Ordering::Equal),
+                    same,
+                    "{} <=> {}",
+                    a.type_name(),
+                    b.type_name()
+                );
+                if same {
+                    assert_eq!(hash(a), hash(b), "hash of {}", a.type_name());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn equality() {
+        let cases = [
+            (number(1.0), number(1.0), true),
+            (number(1.0), number(2.0), false),
+            (number(0.0), number(-0.0), true),
+            (number(f64::NAN), number(f64::NAN), false),
+            (number(f64::INFINITY), number(f64::INFINITY), true),
+            (string("a"), string("a"), true),
+            (string("a"), string("A"), false),
+            (Object::Boolean(true), Object::Boolean(true), true),
+            (Object::Boolean(false), Object::Boolean(false), true),
+            (Object::Boolean(true), Object::Boolean(false), false),
+            (Object::Nil, Object::Nil, true),
+            (Object::Nil, Object::Boolean(false), false),
+            (number(0.0), Object::Boolean(false), false),
+            (number(0.0), Object::Nil, false),
+            (string(""), Object::Nil, false),
+            (string("1"), number(1.0), false),
+            (
+                list::new(vec![number(1.0), string("a")]),
+                list::new(vec![number(1.0), string("a")]),
+                true,
+            ),
+            (list::new(vec![]), list::new(vec![]), true),
+            (
+                list::new(vec![number(1.0)]),
+                list::new(vec![number(1.0), number(2.0)]),
+                false,
+            ),
+            (
+                list::new(vec![number(f64::NAN)]),
+                list::new(vec![number(f64::NAN)]),
+                false,
+            ),
+            (
+                map_of(vec![(string("a"), number(1.0)), (string("b"), number(2.0))]),
+                map_of(vec![(string("b"), number(2.0)), (string("a"), number(1.0))]),
+                true,
+            ),
+            (
+                map_of(vec![(string("a"), number(1.0))]),
+                map_of(vec![(string("a"), number(2.0))]),
+                false,
+            ),
+            (
+                map_of(vec![(string("a"), number(1.0))]),
+                map_of(vec![(string("b"), number(1.0))]),
+                false,
+            ),
+            (map_of(vec![]), list::new(vec![]), false),
+        ];
+        for (a, b, equal) in cases {
+            assert_eq!(a == b, equal, "{} == {}", a, b);
+            assert_eq!(b == a, equal, "{} == {}", b, a);
+            if equal {
+                assert_eq!(hash(&a), hash(&b), "hash of {} and {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn callables_classes_and_instances_compare_by_identity() {
+        let point = class("Point");
+        let function = native();
+        assert!(function == function.clone());
+        assert!(native() != native());
+        assert!(Object::Class(Rc::clone(&point)) == Object::Class(Rc::clone(&point)));
+        assert!(Object::Class(point.clone()) != Object::Class(class("Point")));
+        let first = instance(&point);
+        assert!(first == first.clone());
+        assert!(first != instance(&point));
+    }
+
+    #[test]
+    fn a_list_containing_itself_equals_itself() {
+        let list = list::new(vec![]);
+        if let Object::List(elements) = &list {
+            elements.borrow_mut().push(list.clone());
+        }
+        assert!(list == list.clone());
+        assert_eq!(hash(&list), hash(&list.clone()));
+    }
+
+    #[test]
+    fn distinct_collections_containing_themselves() {
+        let cyclic_list = |extra: Vec<Object>| {
+            let list = list::new(extra);
+            if let Object::List(elements) = &list {
+                elements.borrow_mut().push(list.clone());
+            }
+            list
+        };
+        assert!(cyclic_list(vec![]) == cyclic_list(vec![]));
+        assert!(cyclic_list(vec![number(1.0)]) == cyclic_list(vec![number(1.0)]));
+        assert!(cyclic_list(vec![number(1.0)]) != cyclic_list(vec![number(2.0)]));
+        assert!(cyclic_list(vec![]) != cyclic_list(vec![number(1.0)]));
+
+        let cyclic_map = |value: f64| {
+            let map = map_of(vec![(string("n"), number(value))]);
+            if let Object::Map(entries) = &map {
+                entries
+                    .borrow_mut()
+                    .insert(string("self"), map.clone(), Span::default())
+                    .unwrap();
+            }
+            map
+        };
+        assert!(cyclic_map(1.0) == cyclic_map(1.0));
+        assert!(cyclic_map(1.0) != cyclic_map(2.0));
+    }
+
+    #[test]
+    fn ordering() {
+        let cases = [
+            (number(1.0), number(2.0), Some(Ordering::Less)),
+            (number(2.0), number(1.0), Some(Ordering::Greater)),
+            (number(-0.0), number(0.0), Some(Ordering::Equal)),
+            (number(f64::NAN), number(1.0), None),
+            (number(f64::NAN), number(f64::NAN), None),
+            (string("a"), string("b"), Some(Ordering::Less)),
+            (string("b"), string("abc"), Some(Ordering::Greater)),
+            (string(""), string("a"), Some(Ordering::Less)),
+            (string("Z"), string("a"), Some(Ordering::Less)),
+            (string("a"), string("a"), Some(Ordering::Equal)),
+            (number(1.0), string("1"), None),
+            (Object::Boolean(false), Object::Boolean(true), None),
+            (Object::Nil, Object::Nil, Some(Ordering::Equal)),
+            (Object::Nil, number(0.0), None),
+            (list::new(vec![]), list::new(vec![number(1.0)]), None),
+        ];
+        for (a, b, ordering) in cases {
+            assert_eq!(a.partial_cmp(&b), ordering, "{} <=> {}", a, b);
+            assert_eq!(
+                b.partial_cmp(&a),
+                ordering.map(Ordering::reverse),
+                "{} <=> {}",
+                b,
+                a
+            );
         }
     }
 }
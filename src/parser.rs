@@ -1,10 +1,14 @@
 pub use crate::error::ParserError;
-pub use crate::expr::{Expr, IfStmt, LiteralValue, Stmt, Var, WhileStmt};
-pub use crate::token::{Token, TokenType};
+pub use crate::expr::{Expr, LiteralValue, Stmt};
+pub use crate::token::{Span, Token, TokenType};
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    errors: Vec<ParserError>,
+    // How many loops enclose the current statement within this function,
+    // so `break` and `continue` can be rejected outside of one.
+    loop_depth: usize,
 }
 
 macro_rules! matches {
@@ -22,23 +26,73 @@ macro_rules! matches {
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Parser {
-        Parser { tokens, current: 0 }
+        Parser {
+            tokens,
+            current: 0,
+            errors: Vec::new(),
+            loop_depth: 0,
+        }
     }
 
-    // TODO catch ParseEerror return null
-    pub fn parse(&mut self) -> Vec<Stmt> {
+    // Parses the whole program. Every error is collected, and the program is
+    // only handed back if there were none.
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<ParserError>> {
         let mut statements: Vec<Stmt> = Vec::new();
         while !self.is_at_end() {
-            match self.declaration() {
-                Ok(stmt) => statements.push(stmt),
-                Err(err) => println!("{:?}", err),
+            if let Some(stmt) = self.declaration_or_recover() {
+                statements.push(stmt);
+            }
+        }
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    // Records the error and skips ahead to the next statement boundary, so
+    // one mistake doesn't cascade into a pile of bogus errors.
+    fn declaration_or_recover(&mut self) -> Option<Stmt> {
+        match self.declaration() {
+            Ok(stmt) => Some(stmt),
+            Err(err) => {
+                self.errors.push(err);
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    fn synchronize(&mut self) {
+        self.advance();
+        while !self.is_at_end() {
+            if self.previous().token_type == TokenType::Semicolon {
+                return;
+            }
+            match self.peek().token_type {
+                TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return
+                | TokenType::Throw
+                | TokenType::Try => return,
+                _ => {
+                    self.advance();
+                }
             }
         }
-        return statements;
     }
 
     fn declaration(&mut self) -> Result<Stmt, ParserError> {
-        if matches!(self, TokenType::Fun) {
+        if matches!(self, TokenType::Class) {
+            self.class_declaration()
+        // `fun (` starts an anonymous function used as an expression statement.
+        } else if self.check(TokenType::Fun) && !self.check_next(TokenType::LeftParen) {
+            self.advance();
             self.function("function")
         } else if matches!(self, TokenType::Var) {
             self.var_declaration()
@@ -47,6 +101,38 @@ impl Parser {
         }
     }
 
+    fn class_declaration(&mut self) -> Result<Stmt, ParserError> {
+        let name = self.consume(TokenType::Identifier, "Expect class name.".to_string())?;
+
+        let superclass = if matches!(self, TokenType::Less) {
+            let token =
+                self.consume(TokenType::Identifier, "Expect superclass name.".to_string())?;
+            Some(Expr::Variable { token, depth: None })
+        } else {
+            None
+        };
+
+        self.consume(
+            TokenType::LeftBrace,
+            "Expect '{' before class body.".to_string(),
+        )?;
+
+        let mut methods: Vec<Stmt> = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            methods.push(self.function("method")?);
+        }
+        self.consume(
+            TokenType::RightBrace,
+            "Expect '}' after class body.".to_string(),
+        )?;
+
+        Ok(Stmt::Class {
+            name,
+            superclass,
+            methods,
+        })
+    }
+
     fn var_declaration(&mut self) -> Result<Stmt, ParserError> {
         let name = self.consume(TokenType::Identifier, "Expect variable name.".to_string())?;
 
@@ -61,22 +147,14 @@ impl Parser {
             "Expect ';' after variable declaration.".to_string(),
         )?;
 
-        return Ok(Stmt::Var {
-            name: name,
+        Ok(Stmt::Var {
+            name,
             initializer: value,
-        });
-    }
-
-    fn expression(&mut self) -> Result<Expr, ParserError> {
-        self.assignment()
+        })
     }
 
-    fn function(&mut self, kind: &str) -> Result<Stmt, ParserError> {
-        let name = self.consume(TokenType::Identifier, format!("Expect {} name.", kind))?;
-        self.consume(
-            TokenType::LeftParen,
-            format!("Expect '(' after {} name.", kind),
-        )?;
+    // Everything between '(' and ')', consuming the ')'.
+    fn parameters(&mut self) -> Result<Vec<Token>, ParserError> {
         let mut parameters: Vec<Token> = Vec::new();
         if !self.check(TokenType::RightParen) {
             loop {
@@ -99,61 +177,171 @@ impl Parser {
             TokenType::RightParen,
             "Expect ')' after parameters".to_string(),
         )?;
+        Ok(parameters)
+    }
 
+    fn lambda(&mut self) -> Result<Expr, ParserError> {
+        let keyword = self.advance();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'fun'.".to_string())?;
+        let params = self.parameters()?;
         self.consume(
             TokenType::LeftBrace,
-            format!("Expect `{{` before {} body", kind),
+            "Expect '{' before function body.".to_string(),
         )?;
-        // TODO fix this into something prettier. Block_statement could return a
-        // vector
-        if let Stmt::Block { statements } = self.block_statement()? {
-            return Ok(Stmt::Function {
-                name,
-                params: parameters,
-                body: statements,
-            });
+        Ok(Expr::Lambda {
+            keyword,
+            params,
+            body: self.function_body()?,
+        })
+    }
+
+    fn arrow_function(&mut self) -> Result<Expr, ParserError> {
+        self.consume(TokenType::LeftParen, "Expect '('.".to_string())?;
+        let params = self.parameters()?;
+        let keyword = self.consume(
+            TokenType::Arrow,
+            "Expect '=>' after parameters.".to_string(),
+        )?;
+        let body = if matches!(self, TokenType::LeftBrace) {
+            self.function_body()?
         } else {
-            Err(ParserError {
-                token: self.peek(),
-                message: "Unexpected error caling function".to_string(),
-            })
+            let value = self.expression()?;
+            vec![Stmt::Return {
+                keyword: keyword.clone(),
+                value: Some(value),
+            }]
+        };
+        Ok(Expr::Lambda {
+            keyword,
+            params,
+            body,
+        })
+    }
+
+    // Looks past a '(' for `(a, b) =>` without consuming anything, since
+    // the same '(' could start a grouping.
+    fn is_arrow_function(&self) -> bool {
+        let mut i = self.current + 1;
+        let token_type = |i: usize| self.tokens.get(i).map(|token| &token.token_type);
+        if token_type(i) != Some(&TokenType::RightParen) {
+            loop {
+                if token_type(i) != Some(&TokenType::Identifier) {
+                    return false;
+                }
+                i += 1;
+                match token_type(i) {
+                    Some(TokenType::Comma) => i += 1,
+                    Some(TokenType::RightParen) => break,
+                    _ => return false,
+                }
+            }
         }
-        //let block = self.block_statement()?;
-        //        let body = match self.block_statement()? {
-        //            Stmt::Block { statements } => statements,
-        //            _ => Vec::new(),
-        //        };
+        token_type(i + 1) == Some(&TokenType::Arrow)
+    }
+
+    fn expression(&mut self) -> Result<Expr, ParserError> {
+        self.assignment()
+    }
 
-        //if let Stmt::Block(body) = self.block_statement()? {
-        //    Ok(Stmt::Function(name, parameters, body))
-        //} else {
-        //    // unreachable code, needed to make the compiler happy
-        //    Err(ParseError::UnexpectedError)
-        //}
+    fn function(&mut self, kind: &str) -> Result<Stmt, ParserError> {
+        let name = self.consume(TokenType::Identifier, format!("Expect {} name.", kind))?;
+        self.consume(
+            TokenType::LeftParen,
+            format!("Expect '(' after {} name.", kind),
+        )?;
+        let parameters = self.parameters()?;
+        self.consume(
+            TokenType::LeftBrace,
+            format!("Expect `{{` before {} body", kind),
+        )?;
+        Ok(Stmt::Function {
+            name,
+            params: parameters,
+            body: self.function_body()?,
+        })
     }
 
     fn assignment(&mut self) -> Result<Expr, ParserError> {
         let expr: Expr = self.or()?;
 
-        while matches!(self, TokenType::Equal) {
+        if matches!(self, TokenType::Equal) {
             let equals = self.previous();
             let value = self.assignment()?;
-            match expr {
-                Expr::Variable { token } => {
-                    return Ok(Expr::Assign {
-                        name: token,
-                        value: Box::new(value),
-                    });
-                }
-                _ => {
-                    return Err(ParserError {
-                        token: equals,
-                        message: "Invalid assignment target".to_string(),
-                    });
-                }
-            }
+            return match expr {
+                Expr::Variable { token, .. } => Ok(Expr::Assign {
+                    name: token,
+                    value: Box::new(value),
+                    depth: None,
+                }),
+                Expr::Get { object, name } => Ok(Expr::Set {
+                    object,
+                    name,
+                    value: Box::new(value),
+                    operator: None,
+                }),
+                Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                } => Ok(Expr::SetIndex {
+                    object,
+                    bracket,
+                    index,
+                    value: Box::new(value),
+                    operator: None,
+                }),
+                _ => Err(ParserError {
+                    token: equals,
+                    message: "Invalid assignment target".to_string(),
+                }),
+            };
         }
-        return Ok(expr);
+
+        if matches!(
+            self,
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+            TokenType::PercentEqual
+        ) {
+            let equals = self.previous();
+            let operator = compound_operator(equals.clone());
+            let value = self.assignment()?;
+            return match expr {
+                Expr::Variable { token, .. } => Ok(Expr::Assign {
+                    name: token.clone(),
+                    value: Box::new(Expr::Binary {
+                        left: Box::new(Expr::Variable { token, depth: None }),
+                        operator,
+                        right: Box::new(value),
+                    }),
+                    depth: None,
+                }),
+                Expr::Get { object, name } => Ok(Expr::Set {
+                    object,
+                    name,
+                    value: Box::new(value),
+                    operator: Some(operator),
+                }),
+                Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                } => Ok(Expr::SetIndex {
+                    object,
+                    bracket,
+                    index,
+                    value: Box::new(value),
+                    operator: Some(operator),
+                }),
+                _ => Err(ParserError {
+                    token: equals,
+                    message: "Invalid assignment target".to_string(),
+                }),
+            };
+        }
+        Ok(expr)
     }
 
     fn or(&mut self) -> Result<Expr, ParserError> {
@@ -168,7 +356,7 @@ impl Parser {
                 right: Box::new(right),
             }
         }
-        return Ok(expr);
+        Ok(expr)
     }
 
     fn and(&mut self) -> Result<Expr, ParserError> {
@@ -183,7 +371,7 @@ impl Parser {
                 right: Box::new(right),
             }
         }
-        return Ok(expr);
+        Ok(expr)
     }
 
     fn statement(&mut self) -> Result<Stmt, ParserError> {
@@ -202,75 +390,75 @@ impl Parser {
         if matches!(self, TokenType::While) {
             return self.while_statement();
         }
+        if matches!(self, TokenType::Throw) {
+            return self.throw_statement();
+        }
+        if matches!(self, TokenType::Break, TokenType::Continue) {
+            return self.loop_jump_statement();
+        }
+        if matches!(self, TokenType::Try) {
+            return self.try_statement();
+        }
         if matches!(self, TokenType::LeftBrace) {
             return self.block_statement();
         }
-        return self.expression_statement();
+        self.expression_statement()
     }
 
     fn for_statement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.previous().span;
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'.".to_string())?;
         let initializer = if matches!(self, TokenType::Semicolon) {
             None
         } else if matches!(self, TokenType::Var) {
-            Some(self.var_declaration())
+            Some(self.var_declaration()?)
         } else {
-            Some(self.expression_statement())
+            Some(self.expression_statement()?)
         };
 
-        let mut condition = if matches!(self, TokenType::Semicolon) {
-            None
+        let condition = if self.check(TokenType::Semicolon) {
+            Expr::Literal {
+                literal_value: LiteralValue::Boolean(true),
+                span: keyword,
+            }
         } else {
-            Some(self.expression())
+            self.expression()?
         };
         self.consume(
             TokenType::Semicolon,
             "Expect ';' after loop condition.".to_string(),
         )?;
 
-        let increment = if matches!(self, TokenType::RightParen) {
+        let increment = if self.check(TokenType::RightParen) {
             None
         } else {
-            Some(self.expression())
+            Some(self.expression()?)
         };
-
         self.consume(
             TokenType::RightParen,
             "Expect ')' after for clause.".to_string(),
         )?;
 
-        let mut body = self.statement()?;
-        if increment.is_some() {
-            body = Stmt::Block {
-                statements: vec![
-                    Stmt::Expression {
-                        expr: increment.unwrap()?,
-                    },
-                    body,
-                ],
-            };
-        }
-
-        if condition.is_none() {
-            condition = Some(Ok(Expr::Literal {
-                literal_value: LiteralValue::Boolean(true),
-            }));
-        }
-
-        body = Stmt::WhileStmt {
-            condition: condition.unwrap()?,
+        let body = self.loop_body()?;
+        let span = self.span_from(keyword);
+        let while_loop = Stmt::WhileStmt {
+            condition,
             body: Box::new(body),
+            increment,
+            span,
         };
 
-        if initializer.is_some() {
-            body = Stmt::Block {
-                statements: vec![initializer.unwrap()?, body],
-            };
+        match initializer {
+            Some(initializer) => Ok(Stmt::Block {
+                statements: vec![initializer, while_loop],
+                span,
+            }),
+            None => Ok(while_loop),
         }
-        return Ok(body);
     }
 
     fn while_statement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.previous().span;
         self.consume(
             TokenType::LeftParen,
             "Expect '(' after 'while'.".to_string(),
@@ -282,17 +470,48 @@ impl Parser {
             TokenType::RightParen,
             "Expect ')' after condition.".to_string(),
         )?;
-        let body = self.statement()?;
-        return Ok(Stmt::WhileStmt {
-            condition: condition,
+        let body = self.loop_body()?;
+        Ok(Stmt::WhileStmt {
+            condition,
             body: Box::new(body),
-        });
+            increment: None,
+            span: self.span_from(keyword),
+        })
+    }
+
+    fn loop_body(&mut self) -> Result<Stmt, ParserError> {
+        self.loop_depth += 1;
+        let body = self.statement();
+        self.loop_depth -= 1;
+        body
+    }
+
+    // `break;` and `continue;`. Outside a loop the error is recorded but
+    // parsing carries on, there is nothing to recover from.
+    fn loop_jump_statement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.previous();
+        self.consume(
+            TokenType::Semicolon,
+            format!("Expect ';' after '{}'.", keyword.lexeme),
+        )?;
+        if self.loop_depth == 0 {
+            self.errors.push(ParserError {
+                message: format!("Can't use '{}' outside of a loop.", keyword.lexeme),
+                token: keyword.clone(),
+            });
+        }
+        if keyword.token_type == TokenType::Break {
+            Ok(Stmt::Break { keyword })
+        } else {
+            Ok(Stmt::Continue { keyword })
+        }
     }
 
     fn if_statement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.previous().span;
         self.consume(TokenType::LeftParen, "Expect '(' after 'if'.".to_string())?;
         match self.expression() {
-            Err(err) => return Err(err),
+            Err(err) => Err(err),
             Ok(expr) => {
                 self.consume(TokenType::RightParen, "Expect ')' after 'if'.".to_string())?;
                 let then_branch = self.statement()?;
@@ -302,25 +521,30 @@ impl Parser {
                     None
                 };
 
-                return Ok(Stmt::IfStmt {
+                Ok(Stmt::IfStmt {
                     condition: expr,
                     then_branch: Box::new(then_branch),
-                    else_branch: else_branch,
-                });
+                    else_branch,
+                    span: self.span_from(keyword),
+                })
             }
         }
     }
 
     fn print_statement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.previous().span;
         let expr = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ';' after value.".to_string())?;
 
-        return Ok(Stmt::Print { expr });
+        Ok(Stmt::Print {
+            expr,
+            span: self.span_from(keyword),
+        })
     }
 
     fn return_statement(&mut self) -> Result<Stmt, ParserError> {
         let keyword = self.previous().clone();
-        let value = if !matches!(self, TokenType::Semicolon) {
+        let value = if !self.check(TokenType::Semicolon) {
             Some(self.expression()?)
         } else {
             None
@@ -330,24 +554,107 @@ impl Parser {
             TokenType::Semicolon,
             "Expect ';' after return value.".to_string(),
         )?;
-        return Ok(Stmt::Return { keyword, value });
+        Ok(Stmt::Return { keyword, value })
     }
 
     fn expression_statement(&mut self) -> Result<Stmt, ParserError> {
         let expr = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ';' after value.".to_string())?;
-        return Ok(Stmt::Expression { expr });
+        Ok(Stmt::Expression {
+            span: self.span_from(expr.span()),
+            expr,
+        })
+    }
+
+    fn throw_statement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(
+            TokenType::Semicolon,
+            "Expect ';' after thrown value.".to_string(),
+        )?;
+        Ok(Stmt::Throw { keyword, value })
+    }
+
+    fn try_statement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.previous().span;
+        self.consume(TokenType::LeftBrace, "Expect '{' after 'try'.".to_string())?;
+        let body = self.block()?;
+
+        let catch = if matches!(self, TokenType::Catch) {
+            self.consume(
+                TokenType::LeftParen,
+                "Expect '(' after 'catch'.".to_string(),
+            )?;
+            let name = self.consume(
+                TokenType::Identifier,
+                "Expect error variable name.".to_string(),
+            )?;
+            self.consume(
+                TokenType::RightParen,
+                "Expect ')' after error variable.".to_string(),
+            )?;
+            self.consume(
+                TokenType::LeftBrace,
+                "Expect '{' after catch clause.".to_string(),
+            )?;
+            Some((name, self.block()?))
+        } else {
+            None
+        };
+
+        let finally = if matches!(self, TokenType::Finally) {
+            self.consume(
+                TokenType::LeftBrace,
+                "Expect '{' after 'finally'.".to_string(),
+            )?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(ParserError {
+                token: self.peek(),
+                message: "Expect 'catch' or 'finally' after try block.".to_string(),
+            });
+        }
+        Ok(Stmt::Try {
+            body,
+            catch,
+            finally,
+            span: self.span_from(keyword),
+        })
     }
 
     fn block_statement(&mut self) -> Result<Stmt, ParserError> {
+        let brace = self.previous().span;
+        let statements = self.block()?;
+        Ok(Stmt::Block {
+            statements,
+            span: self.span_from(brace),
+        })
+    }
+
+    // A loop around a function doesn't let its body `break` out of it.
+    fn function_body(&mut self) -> Result<Vec<Stmt>, ParserError> {
+        let enclosing_loops = std::mem::take(&mut self.loop_depth);
+        let body = self.block();
+        self.loop_depth = enclosing_loops;
+        body
+    }
+
+    // The statements of a block whose '{' has already been consumed.
+    fn block(&mut self) -> Result<Vec<Stmt>, ParserError> {
         let mut statements: Vec<Stmt> = Vec::new();
 
-        while !self.check(TokenType::RightBrace) {
-            let stmt = self.declaration()?;
-            statements.push(stmt);
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            if let Some(stmt) = self.declaration_or_recover() {
+                statements.push(stmt);
+            }
         }
         self.consume(TokenType::RightBrace, "Expect '}' after block.".to_string())?;
-        return Ok(Stmt::Block { statements });
+        Ok(statements)
     }
 
     fn equality(&mut self) -> Result<Expr, ParserError> {
@@ -362,7 +669,7 @@ impl Parser {
                 right: Box::new(right),
             };
         }
-        return Ok(expr);
+        Ok(expr)
     }
 
     fn comparison(&mut self) -> Result<Expr, ParserError> {
@@ -382,7 +689,7 @@ impl Parser {
                 right: Box::new(right),
             };
         }
-        return Ok(expr);
+        Ok(expr)
     }
 
     fn term(&mut self) -> Result<Expr, ParserError> {
@@ -396,12 +703,12 @@ impl Parser {
                 right: Box::new(right),
             };
         }
-        return Ok(expr);
+        Ok(expr)
     }
 
     fn factor(&mut self) -> Result<Expr, ParserError> {
         let mut expr: Expr = self.unary()?;
-        while matches!(self, TokenType::Slash, TokenType::Star) {
+        while matches!(self, TokenType::Slash, TokenType::Star, TokenType::Percent) {
             let operator = self.previous();
             let right = self.unary()?;
             expr = Expr::Binary {
@@ -410,11 +717,11 @@ impl Parser {
                 right: Box::new(right),
             };
         }
-        return Ok(expr);
+        Ok(expr)
     }
 
     fn unary(&mut self) -> Result<Expr, ParserError> {
-        while matches!(self, TokenType::Bang, TokenType::Minus) {
+        if matches!(self, TokenType::Bang, TokenType::Minus) {
             let operator = self.previous();
             let right = self.unary()?;
 
@@ -423,7 +730,36 @@ impl Parser {
                 right: Box::new(right),
             });
         }
-        return self.call();
+        self.exponent()
+    }
+
+    // Right associative, and tighter than a unary operator on its left, so
+    // `-2 ** 2` is -4 and `2 ** 3 ** 2` is 512.
+    fn exponent(&mut self) -> Result<Expr, ParserError> {
+        let expr = self.update()?;
+        if matches!(self, TokenType::StarStar) {
+            let operator = self.previous();
+            let right = self.unary()?;
+            return Ok(Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            });
+        }
+        Ok(expr)
+    }
+
+    fn update(&mut self) -> Result<Expr, ParserError> {
+        if matches!(self, TokenType::PlusPlus, TokenType::MinusMinus) {
+            let operator = self.previous();
+            let target = self.call()?;
+            return update_target(operator, target, true);
+        }
+        let expr = self.call()?;
+        if matches!(self, TokenType::PlusPlus, TokenType::MinusMinus) {
+            return update_target(self.previous(), expr, false);
+        }
+        Ok(expr)
     }
 
     fn call(&mut self) -> Result<Expr, ParserError> {
@@ -431,11 +767,59 @@ impl Parser {
         loop {
             if matches!(self, TokenType::LeftParen) {
                 expr = self.finish_call(expr)?;
+            } else if matches!(self, TokenType::Dot) {
+                let name = self.consume(
+                    TokenType::Identifier,
+                    "Expect property name after '.'.".to_string(),
+                )?;
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name,
+                };
+            } else if matches!(self, TokenType::LeftBracket) {
+                expr = self.index(expr)?;
             } else {
                 break;
             }
         }
-        return Ok(expr);
+        Ok(expr)
+    }
+
+    // `xs[i]`, or a slice `xs[start:end]` where either bound can be left out.
+    fn index(&mut self, object: Expr) -> Result<Expr, ParserError> {
+        let bracket = self.previous();
+        let start = if self.check(TokenType::Colon) {
+            None
+        } else {
+            Some(Box::new(self.expression()?))
+        };
+        if matches!(self, TokenType::Colon) {
+            let end = if self.check(TokenType::RightBracket) {
+                None
+            } else {
+                Some(Box::new(self.expression()?))
+            };
+            self.consume(
+                TokenType::RightBracket,
+                "Expect ']' after slice.".to_string(),
+            )?;
+            return Ok(Expr::Slice {
+                object: Box::new(object),
+                bracket,
+                start,
+                end,
+            });
+        }
+        self.consume(
+            TokenType::RightBracket,
+            "Expect ']' after index.".to_string(),
+        )?;
+        Ok(Expr::Index {
+            object: Box::new(object),
+            bracket,
+            // Only missing when a ':' follows, which makes it a slice.
+            index: start.unwrap(),
+        })
     }
 
     fn finish_call(&mut self, callee: Expr) -> Result<Expr, ParserError> {
@@ -459,50 +843,80 @@ impl Parser {
             TokenType::RightParen,
             "Expect ')' after arguments.".to_string(),
         )?;
-        return Ok(Expr::Call {
+        Ok(Expr::Call {
             callee: Box::new(callee),
             paren,
-            arguments: Box::new(arguments),
-        });
+            arguments,
+        })
     }
 
     fn primary(&mut self) -> Result<Expr, ParserError> {
         let expr = match self.peek().token_type {
             TokenType::False => Expr::Literal {
                 literal_value: LiteralValue::Boolean(false),
+                span: self.peek().span,
             },
             TokenType::True => Expr::Literal {
                 literal_value: LiteralValue::Boolean(true),
+                span: self.peek().span,
             },
             TokenType::Nil => Expr::Literal {
                 literal_value: LiteralValue::Null,
+                span: self.peek().span,
             },
             TokenType::Number { literal } => Expr::Literal {
                 literal_value: LiteralValue::Number(literal),
+                span: self.peek().span,
             },
             TokenType::String { literal } => Expr::Literal {
                 literal_value: LiteralValue::String(literal),
+                span: self.peek().span,
             },
+            TokenType::Interpolation { .. } => return self.interpolation(),
+            TokenType::LeftBracket => return self.list(),
+            // Where an expression is expected a '{' can't start a block.
+            TokenType::LeftBrace => return self.map(),
+            TokenType::Fun => return self.lambda(),
+            TokenType::LeftParen if self.is_arrow_function() => return self.arrow_function(),
             TokenType::LeftParen => {
+                let paren = self.advance().span;
                 let expr = self.expression()?;
                 self.consume(
                     TokenType::RightParen,
                     "Expect ')' after expression.".to_string(),
                 )?;
-                Expr::Grouping {
+                return Ok(Expr::Grouping {
                     group: Box::new(expr),
-                }
+                    span: self.span_from(paren),
+                });
+            }
+            TokenType::Identifier => Expr::Variable {
+                token: self.peek(),
+                depth: None,
+            },
+            TokenType::This => Expr::This {
+                keyword: self.peek(),
+                depth: None,
+            },
+            TokenType::Super => {
+                let keyword = self.advance();
+                self.consume(TokenType::Dot, "Expect '.' after 'super'.".to_string())?;
+                let method = self.consume(
+                    TokenType::Identifier,
+                    "Expect superclass method name.".to_string(),
+                )?;
+                return Ok(Expr::Super {
+                    keyword,
+                    method,
+                    depth: None,
+                });
             }
-            TokenType::Identifier => Expr::Variable { token: self.peek() },
             _ => {
-                self.advance();
+                // Leave the offending token in place so `synchronize` can
+                // decide whether it already starts the next statement.
                 return Err(ParserError {
-                    token: self.previous(),
-                    message: format!(
-                        "Expected valid expression at position {}, Current: {}",
-                        self.current,
-                        self.peek(),
-                    ),
+                    token: self.peek(),
+                    message: "Expect expression.".to_string(),
                 });
             }
         };
@@ -510,6 +924,110 @@ impl Parser {
         Ok(expr)
     }
 
+    // `[a, b, c]`, a trailing comma is allowed.
+    fn list(&mut self) -> Result<Expr, ParserError> {
+        let bracket = self.advance().span;
+        let mut elements = Vec::new();
+        while !self.check(TokenType::RightBracket) {
+            elements.push(self.expression()?);
+            if !matches!(self, TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(
+            TokenType::RightBracket,
+            "Expect ']' after list elements.".to_string(),
+        )?;
+        Ok(Expr::List {
+            elements,
+            span: self.span_from(bracket),
+        })
+    }
+
+    // `{"a": 1, "b": 2}`, a trailing comma is allowed.
+    fn map(&mut self) -> Result<Expr, ParserError> {
+        let brace = self.advance().span;
+        let mut entries = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            let key = self.expression()?;
+            self.consume(TokenType::Colon, "Expect ':' after map key.".to_string())?;
+            entries.push((key, self.expression()?));
+            if !matches!(self, TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(
+            TokenType::RightBrace,
+            "Expect '}' after map entries.".to_string(),
+        )?;
+        Ok(Expr::Map {
+            entries,
+            span: self.span_from(brace),
+        })
+    }
+
+    // Lowers `"a${b}c"` into `"a" + str(b) + "c"`. The conversion is a unary
+    // expression whose operator is the `Interpolation` token before it, so
+    // it can't be affected by a script redefining `str`.
+    fn interpolation(&mut self) -> Result<Expr, ParserError> {
+        let mut part = self.advance();
+        let mut expr = self.string_literal(&part);
+        loop {
+            let value = self.expression()?;
+            let converted = Expr::Unary {
+                operator: part.clone(),
+                right: Box::new(value),
+            };
+            expr = self.concatenate(expr, converted, &part);
+
+            part = self.advance();
+            match part.token_type {
+                TokenType::Interpolation { .. } => {
+                    let text = self.string_literal(&part);
+                    expr = self.concatenate(expr, text, &part);
+                }
+                TokenType::String { .. } => {
+                    let text = self.string_literal(&part);
+                    return Ok(self.concatenate(expr, text, &part));
+                }
+                _ => {
+                    return Err(ParserError {
+                        token: part,
+                        message: "Expect '}' after interpolated expression.".to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    fn string_literal(&self, token: &Token) -> Expr {
+        let literal = match &token.token_type {
+            TokenType::String { literal } | TokenType::Interpolation { literal } => literal.clone(),
+            _ => String::new(),
+        };
+        Expr::Literal {
+            literal_value: LiteralValue::String(literal),
+            span: token.span,
+        }
+    }
+
+    fn concatenate(&self, left: Expr, right: Expr, part: &Token) -> Expr {
+        Expr::Binary {
+            left: Box::new(left),
+            operator: Token {
+                token_type: TokenType::Plus,
+                lexeme: "+".to_string(),
+                span: part.span,
+            },
+            right: Box::new(right),
+        }
+    }
+
+    // Span from `start` up to the end of the last consumed token.
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.previous().span)
+    }
+
     fn check(&self, token_type: TokenType) -> bool {
         if self.is_at_end() {
             return false;
@@ -528,6 +1046,12 @@ impl Parser {
         self.peek().token_type == TokenType::Eof
     }
 
+    fn check_next(&self, token_type: TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| token.token_type == token_type)
+    }
+
     fn peek(&self) -> Token {
         self.tokens.get(self.current).cloned().expect("No previous")
     }
@@ -541,12 +1065,46 @@ impl Parser {
 
     fn consume(&mut self, token_type: TokenType, message: String) -> Result<Token, ParserError> {
         if self.check(token_type) {
-            return Ok(self.advance());
+            Ok(self.advance())
         } else {
-            return Err(ParserError {
+            Err(ParserError {
                 token: self.peek(),
                 message,
-            });
+            })
         }
     }
 }
+
+// The binary operator a compound assignment like `+=` applies. It keeps the
+// original lexeme and span for error messages.
+fn compound_operator(token: Token) -> Token {
+    let token_type = match token.token_type {
+        TokenType::PlusEqual => TokenType::Plus,
+        TokenType::MinusEqual => TokenType::Minus,
+        TokenType::StarEqual => TokenType::Star,
+        TokenType::SlashEqual => TokenType::Slash,
+        TokenType::PercentEqual => TokenType::Percent,
+        _ => unreachable!(),
+    };
+    Token {
+        token_type,
+        ..token
+    }
+}
+
+fn update_target(operator: Token, target: Expr, prefix: bool) -> Result<Expr, ParserError> {
+    match target {
+        Expr::Variable { .. } | Expr::Get { .. } | Expr::Index { .. } => Ok(Expr::Update {
+            target: Box::new(target),
+            operator,
+            prefix,
+        }),
+        _ => Err(ParserError {
+            message: format!(
+                "Can only apply '{}' to a variable, property or index.",
+                operator.lexeme
+            ),
+            token: operator,
+        }),
+    }
+}
@@ -0,0 +1,105 @@
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::callable::Arity;
+use crate::error::RuntimeError;
+use crate::interpreter::Interpreter;
+use crate::object::Object;
+
+// Native functions every script starts with. They live in `globals`, so a
+// script can shadow any of them with its own declaration.
+pub fn install(interpreter: &mut Interpreter) {
+    // Caught runtime errors are instances of this. Scripts can subclass it
+    // for their own errors.
+    let error_class = Object::Class(Rc::clone(&interpreter.error_class));
+    interpreter
+        .globals
+        .borrow_mut()
+        .define("Error".to_string(), error_class);
+
+    interpreter.define_native("clock", Arity::Fixed(0), |_, _| {
+        Ok(Object::Number(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("Somehow the time broke")
+                .as_secs_f64(),
+        ))
+    });
+
+    interpreter.define_native("type", Arity::Fixed(1), |_, args| {
+        Ok(Object::String(args[0].type_name().to_string()))
+    });
+
+    interpreter.define_native("str", Arity::Fixed(1), |_, args| {
+        Ok(Object::String(args[0].stringify()))
+    });
+
+    interpreter.define_native("num", Arity::Fixed(1), |_, args| match &args[0] {
+        Object::Number(_) => Ok(args[0].clone()),
+        Object::String(text) => match text.trim().parse::<f64>() {
+            Ok(number) => Ok(Object::Number(number)),
+            Err(_) => Err(RuntimeError::native(format!(
+                "Can't convert '{}' to a number.",
+                text
+            ))),
+        },
+        other => Err(RuntimeError::native(format!(
+            "Can't convert a {} to a number.",
+            other.type_name()
+        ))),
+    });
+
+    interpreter.define_native("len", Arity::Fixed(1), |_, args| match &args[0] {
+        Object::String(text) => Ok(Object::Number(text.chars().count() as f64)),
+        Object::List(list) => Ok(Object::Number(list.borrow().len() as f64)),
+        Object::Map(map) => Ok(Object::Number(map.borrow().len() as f64)),
+        other => Err(RuntimeError::native(format!(
+            "Can't take the length of a {}.",
+            other.type_name()
+        ))),
+    });
+
+    // Returns the next line without its line ending, or nil at end of input.
+    interpreter.define_native("input", Arity::Range(0, 1), |_, args| {
+        if let Some(prompt) = args.first() {
+            print!("{}", prompt);
+            io::stdout().flush().map_err(io_error)?;
+        }
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).map_err(io_error)? == 0 {
+            return Ok(Object::Nil);
+        }
+        let trimmed = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(trimmed);
+        Ok(Object::String(line))
+    });
+
+    interpreter.define_native("exit", Arity::Range(0, 1), |_, args| {
+        let code = match args.first() {
+            None => 0,
+            Some(Object::Number(code)) => *code as i32,
+            Some(other) => {
+                return Err(RuntimeError::native(format!(
+                    "Exit code must be a number, not a {}.",
+                    other.type_name()
+                )))
+            }
+        };
+        Err(RuntimeError::exit(code))
+    });
+
+    interpreter.define_native("assert", Arity::Range(1, 2), |_, args| {
+        if args[0].is_truthy() {
+            return Ok(Object::Nil);
+        }
+        Err(RuntimeError::native(match args.get(1) {
+            Some(message) => format!("Assertion failed: {}", message),
+            None => "Assertion failed.".to_string(),
+        }))
+    });
+}
+
+fn io_error(error: io::Error) -> RuntimeError {
+    RuntimeError::native(error.to_string())
+}
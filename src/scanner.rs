@@ -1,6 +1,9 @@
-pub use crate::token::{Token, TokenType};
+pub use crate::error::ScanError;
+pub use crate::token::{Span, Token, TokenType, Trivia, TriviaKind, TriviaToken};
 use std::collections::HashMap;
 
+// `start` and `current` are byte offsets into `source`, always on a char
+// boundary. `column` counts chars, so it matches what an editor shows.
 pub struct Scanner {
     keywords: HashMap<&'static str, TokenType>,
     source: String,
@@ -8,11 +11,25 @@ pub struct Scanner {
     current: usize,
     start: usize,
     line: usize,
+    file_id: usize,
+    column: usize,
+    start_line: usize,
+    start_column: usize,
+    // Set by `scan_tokens_with_trivia`. `trivia` collects whitespace and
+    // comments until the next token takes them as its `leading` trivia.
+    keep_trivia: bool,
+    trivia: Vec<Trivia>,
+    leading: Vec<Vec<Trivia>>,
+    unicode_identifiers: bool,
+    errors: Vec<ScanError>,
+    // One entry per `${` still open: where it is, and a count of the `{`
+    // nested inside it, so the `}` that resumes the string can be told apart.
+    interpolations: Vec<(Span, usize)>,
 }
 
 impl Scanner {
-    pub fn new(source: String) -> Scanner {
-        let mut keywords = HashMap::with_capacity(16);
+    pub fn new(source: String, file_id: usize) -> Scanner {
+        let mut keywords = HashMap::with_capacity(22);
         keywords.insert("and", TokenType::And);
         keywords.insert("class", TokenType::Class);
         keywords.insert("else", TokenType::Else);
@@ -29,6 +46,12 @@ impl Scanner {
         keywords.insert("true", TokenType::True);
         keywords.insert("var", TokenType::Var);
         keywords.insert("while", TokenType::While);
+        keywords.insert("try", TokenType::Try);
+        keywords.insert("catch", TokenType::Catch);
+        keywords.insert("finally", TokenType::Finally);
+        keywords.insert("throw", TokenType::Throw);
+        keywords.insert("break", TokenType::Break);
+        keywords.insert("continue", TokenType::Continue);
 
         Scanner {
             keywords,
@@ -37,132 +60,419 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            file_id,
+            column: 1,
+            start_line: 1,
+            start_column: 1,
+            keep_trivia: false,
+            trivia: Vec::new(),
+            leading: Vec::new(),
+            unicode_identifiers: false,
+            errors: Vec::new(),
+            interpolations: Vec::new(),
         }
     }
+
+    // Lets identifiers use letters from any script, e.g. `var päivä`.
+    // Off by default, where identifiers are ASCII only.
+    pub fn with_unicode_identifiers(mut self, enabled: bool) -> Scanner {
+        self.unicode_identifiers = enabled;
+        self
+    }
+
     fn is_at_end(&self) -> bool {
         self.current >= self.source.len()
     }
 
-    pub fn scan_tokens(&mut self) -> Vec<Token> {
+    // Scanning carries on past errors so they can all be reported at once.
+    // Tokens are only worth running if `errors` comes back empty.
+    pub fn scan_tokens(&mut self) -> (Vec<Token>, Vec<ScanError>) {
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.column;
             self.scan_token();
         }
+        for (span, _) in std::mem::take(&mut self.interpolations) {
+            self.error(span, "Unterminated string interpolation.".to_string());
+        }
         let tok = Token {
             token_type: TokenType::Eof,
             lexeme: "".to_string(),
-            line: self.line,
+            span: Span {
+                file_id: self.file_id,
+                start: self.current,
+                end: self.current,
+                line: self.line,
+                column: self.column,
+            },
         };
 
+        if self.keep_trivia {
+            self.leading.push(std::mem::take(&mut self.trivia));
+        }
         self.tokens.push(tok);
-        self.tokens.clone()
+        (self.tokens.clone(), self.errors.clone())
+    }
+
+    // Like `scan_tokens`, but whitespace and comments are kept, so tools
+    // such as formatters can rebuild the exact source.
+    pub fn scan_tokens_with_trivia(&mut self) -> (Vec<TriviaToken>, Vec<ScanError>) {
+        self.keep_trivia = true;
+        let (tokens, errors) = self.scan_tokens();
+        let tokens = std::mem::take(&mut self.leading)
+            .into_iter()
+            .zip(tokens)
+            .map(|(leading, token)| TriviaToken { leading, token })
+            .collect();
+        (tokens, errors)
     }
 
     fn scan_token(&mut self) {
         match self.advance() {
             '(' => self.add_token(TokenType::LeftParen),
             ')' => self.add_token(TokenType::RightParen),
-            '{' => self.add_token(TokenType::LeftBrace),
-            '}' => self.add_token(TokenType::RightBrace),
+            '{' => {
+                if let Some((_, depth)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace)
+            }
+            '}' if self
+                .interpolations
+                .last()
+                .is_some_and(|(_, depth)| *depth == 0) =>
+            {
+                self.interpolations.pop();
+                self.string();
+            }
+            '}' => {
+                if let Some((_, depth)) = self.interpolations.last_mut() {
+                    *depth -= 1;
+                }
+                self.add_token(TokenType::RightBrace)
+            }
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
+            ':' => self.add_token(TokenType::Colon),
             ';' => self.add_token(TokenType::Semicolon),
             ',' => self.add_token(TokenType::Comma),
             '.' => self.add_token(TokenType::Dot),
+            '-' if self.matches('-') => self.add_token(TokenType::MinusMinus),
+            '-' if self.matches('=') => self.add_token(TokenType::MinusEqual),
             '-' => self.add_token(TokenType::Minus),
+            '+' if self.matches('+') => self.add_token(TokenType::PlusPlus),
+            '+' if self.matches('=') => self.add_token(TokenType::PlusEqual),
             '+' => self.add_token(TokenType::Plus),
+            '*' if self.matches('*') => self.add_token(TokenType::StarStar),
+            '*' if self.matches('=') => self.add_token(TokenType::StarEqual),
             '*' => self.add_token(TokenType::Star),
+            '%' if self.matches('=') => self.add_token(TokenType::PercentEqual),
+            '%' => self.add_token(TokenType::Percent),
             '!' if self.matches('=') => self.add_token(TokenType::BangEqual),
             '!' => self.add_token(TokenType::Bang),
             '=' if self.matches('=') => self.add_token(TokenType::EqualEqual),
+            '=' if self.matches('>') => self.add_token(TokenType::Arrow),
             '=' => self.add_token(TokenType::Equal),
             '<' if self.matches('=') => self.add_token(TokenType::LessEqual),
             '<' => self.add_token(TokenType::Less),
             '>' if self.matches('=') => self.add_token(TokenType::GreaterEqual),
             '>' => self.add_token(TokenType::Greater),
-            '/' => self.add_token(TokenType::Slash), //No comment handling
-            'o' if self.matches('r') => self.add_token(TokenType::Or),
-            ' ' => print!(""),
-            '\r' => print!(""),
-            '\n' => self.line += 1,
+            '/' if self.matches('/') => self.line_comment(),
+            '/' if self.matches('*') => self.block_comment(),
+            '/' if self.matches('=') => self.add_token(TokenType::SlashEqual),
+            '/' => self.add_token(TokenType::Slash),
+            ' ' | '\r' | '\t' => self.add_trivia(TriviaKind::Whitespace),
+            '\n' => {
+                self.new_line();
+                self.add_trivia(TriviaKind::Newline);
+            }
             '"' => self.string(),
-            c if is_digit(c) => self.number(),
-            c if is_alpha(c) => self.identifier(),
-            _ => println!("OH no, lexical error!"),
+            c if is_digit(c) => self.number(c),
+            c if self.is_identifier_start(c) => self.identifier(),
+            c => self.error(self.span(), format!("Unexpected character '{}'.", c)),
         }
     }
 
     fn advance(&mut self) -> char {
         let character = self.peek();
-        self.current += 1;
-        return character;
+        self.current += character.len_utf8();
+        self.column += 1;
+        character
     }
 
     fn add_token(&mut self, token_type: TokenType) {
+        if self.keep_trivia {
+            self.leading.push(std::mem::take(&mut self.trivia));
+        }
         let sub_string = self.source[self.start..self.current].to_string();
         self.tokens.push(Token {
             token_type,
             lexeme: sub_string,
-            line: self.line,
+            span: self.span(),
         })
     }
 
+    // Span of the lexeme scanned so far.
+    fn span(&self) -> Span {
+        Span {
+            file_id: self.file_id,
+            start: self.start,
+            end: self.current,
+            line: self.start_line,
+            column: self.start_column,
+        }
+    }
+
+    // Span of the first `length` bytes of the lexeme, e.g. an opening quote,
+    // so an unterminated string doesn't underline the rest of the file.
+    fn opening(&self, length: usize) -> Span {
+        Span {
+            end: self.start + length,
+            ..self.span()
+        }
+    }
+
+    fn error(&mut self, span: Span, message: String) {
+        self.errors.push(ScanError { span, message });
+    }
+
+    fn add_trivia(&mut self, kind: TriviaKind) {
+        if !self.keep_trivia {
+            return;
+        }
+        // A run of spaces and tabs is one piece of trivia, not one per char.
+        if let Some(last) = self.trivia.last_mut() {
+            if kind == TriviaKind::Whitespace && last.kind == kind {
+                last.text.push_str(&self.source[self.start..self.current]);
+                last.span.end = self.current;
+                return;
+            }
+        }
+        self.trivia.push(Trivia {
+            kind,
+            text: self.source[self.start..self.current].to_string(),
+            span: self.span(),
+        });
+    }
+
+    fn line_comment(&mut self) {
+        while self.peek() != '\n' && !self.is_at_end() {
+            self.advance();
+        }
+        self.add_trivia(TriviaKind::LineComment);
+    }
+
+    // Block comments nest, so `/* a /* b */ c */` is a single comment.
+    fn block_comment(&mut self) {
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                self.error(self.opening(2), "Unterminated block comment.".to_string());
+                break;
+            }
+            match self.advance() {
+                '/' if self.matches('*') => depth += 1,
+                '*' if self.matches('/') => depth -= 1,
+                '\n' => self.new_line(),
+                _ => (),
+            }
+        }
+        self.add_trivia(TriviaKind::BlockComment);
+    }
+
+    // Called after consuming a '\n'.
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.column = 1;
+    }
+
     fn matches(&mut self, character: char) -> bool {
-        if self.is_at_end() {
-            return false;
-        } else if self.peek() != character {
+        if self.is_at_end() || self.peek() != character {
             return false;
         }
-        self.current += 1;
+        self.advance();
         true
     }
 
     fn peek(&self) -> char {
-        if self.is_at_end() {
-            return '\0';
-        }
-        return self.source.chars().nth(self.current).expect("shit");
+        self.source[self.current..].chars().next().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() {
-            return '\0';
-        }
-        return self.source.chars().nth(self.current + 1).expect("shit");
+        let mut chars = self.source[self.current..].chars();
+        chars.next();
+        chars.next().unwrap_or('\0')
     }
 
+    // Scans from just after the opening '"', or after the '}' closing an
+    // interpolated expression, up to the closing '"' or the next `${`.
     fn string(&mut self) {
-        while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+        let mut literal = String::new();
+        loop {
+            if self.is_at_end() {
+                self.error(self.opening(1), "Unterminated string.".to_string());
+                return;
+            }
+            match self.advance() {
+                '"' => break,
+                '$' if self.matches('{') => {
+                    let opening = Span {
+                        start: self.current - 2,
+                        line: self.line,
+                        column: self.column - 2,
+                        ..self.span()
+                    };
+                    self.interpolations.push((opening, 0));
+                    self.add_token(TokenType::Interpolation { literal });
+                    return;
+                }
+                '\\' => {
+                    if let Some(c) = self.escape() {
+                        literal.push(c);
+                    }
+                }
+                '\n' => {
+                    self.new_line();
+                    literal.push('\n');
+                }
+                c => literal.push(c),
             }
-            self.advance();
-        }
-        if self.is_at_end() {
-            println!("Errori!!");
-            return;
         }
-        self.advance();
-        let literal = self.source[self.start + 1..self.current - 1].to_string();
         self.add_token(TokenType::String { literal });
     }
 
-    fn number(&mut self) {
-        while is_digit(self.peek()) {
-            self.advance();
+    // Called after a '\\' inside a string. Reports bad escapes and returns
+    // None for them, so the rest of the string still scans.
+    fn escape(&mut self) -> Option<char> {
+        let start = Span {
+            start: self.current - 1,
+            end: self.current,
+            line: self.line,
+            column: self.column - 1,
+            ..self.span()
+        };
+        let c = match self.peek() {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '$' => '$',
+            'u' => {
+                self.advance();
+                return self.unicode_escape(start);
+            }
+            // The unterminated string is reported by the caller.
+            _ if self.is_at_end() => return None,
+            _ => {
+                let c = self.peek();
+                if c != '\n' {
+                    self.advance();
+                }
+                let span = Span {
+                    end: self.current,
+                    ..start
+                };
+                self.error(span, format!("Invalid escape sequence '\\{}'.", c));
+                return None;
+            }
+        };
+        self.advance();
+        Some(c)
+    }
+
+    // `\u{...}` with one to six hex digits naming a Unicode scalar value.
+    fn unicode_escape(&mut self, start: Span) -> Option<char> {
+        let mut digits = String::new();
+        let closed = if self.matches('{') {
+            while self.peek().is_ascii_hexdigit() {
+                digits.push(self.advance());
+            }
+            self.matches('}')
+        } else {
+            false
+        };
+        let c = u32::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| closed && digits.len() <= 6)
+            .and_then(char::from_u32);
+        if c.is_none() {
+            let span = Span {
+                end: self.current,
+                ..start
+            };
+            self.error(
+                span,
+                "Invalid Unicode escape, expected '\\u{' followed by 1 to 6 hex digits and '}'."
+                    .to_string(),
+            );
         }
-        if self.peek() == '.' && is_digit(self.peek_next()) {
-            self.advance();
-            while is_digit(self.peek()) {
+        c
+    }
+
+    // Decimal numbers may have a fraction and an exponent. `0x` and `0b`
+    // start hex and binary integers. Any of them can use '_' between digits.
+    fn number(&mut self, first: char) {
+        let mut radix = 10;
+        if first == '0' && matches!(self.peek(), 'x' | 'X' | 'b' | 'B') {
+            radix = if matches!(self.advance(), 'x' | 'X') {
+                16
+            } else {
+                2
+            };
+            self.digits(radix);
+        } else {
+            self.digits(10);
+            if self.peek() == '.' && is_digit(self.peek_next()) {
+                self.advance();
+                self.digits(10);
+            }
+            if matches!(self.peek(), 'e' | 'E') {
                 self.advance();
+                if matches!(self.peek(), '+' | '-') {
+                    self.advance();
+                }
+                self.digits(10);
             }
         }
-        let literal: f64 = self.source[self.start..self.current]
-            .parse()
-            .expect("invalid number");
-        self.add_token(TokenType::Number { literal })
+        // Swallow letters stuck to the number so `0xFG` or `12ab` is
+        // reported as one bad literal rather than a number and a name.
+        while self.is_identifier_part(self.peek()) {
+            self.advance();
+        }
+
+        match parse_number(&self.source[self.start..self.current], radix) {
+            Ok(literal) => self.add_token(TokenType::Number { literal }),
+            Err(message) => self.error(self.span(), message),
+        }
+    }
+
+    fn digits(&mut self, radix: u32) {
+        while self.peek().is_digit(radix) || self.peek() == '_' {
+            self.advance();
+        }
+    }
+
+    fn is_identifier_start(&self, c: char) -> bool {
+        if self.unicode_identifiers {
+            is_unicode_alpha(c)
+        } else {
+            is_alpha(c)
+        }
+    }
+
+    fn is_identifier_part(&self, c: char) -> bool {
+        if self.unicode_identifiers {
+            is_unicode_alpha_numeric(c)
+        } else {
+            is_alpha_numeric(c)
+        }
     }
 
     fn identifier(&mut self) {
-        while is_alpha_numeric(self.peek()) {
+        while self.is_identifier_part(self.peek()) {
             self.advance();
         }
         let literal = &self.source[self.start..self.current];
@@ -176,14 +486,200 @@ impl Scanner {
     }
 }
 
+fn parse_number(text: &str, radix: u32) -> Result<f64, String> {
+    let chars: Vec<char> = text.chars().collect();
+    for (i, c) in chars.iter().enumerate() {
+        let between_digits = i > 0
+            && i + 1 < chars.len()
+            && chars[i - 1].is_digit(radix)
+            && chars[i + 1].is_digit(radix);
+        if *c == '_' && !between_digits {
+            return Err(format!(
+                "Misplaced '_' in number '{}', it can only separate digits.",
+                text
+            ));
+        }
+    }
+    let digits = text.replace('_', "");
+    if radix == 10 {
+        return digits
+            .parse()
+            .map_err(|_| format!("Malformed number '{}'.", text));
+    }
+
+    let (prefix, digits) = digits.split_at(2);
+    if digits.is_empty() {
+        return Err(format!("Expect digits after '{}' in number.", prefix));
+    }
+    match u64::from_str_radix(digits, radix) {
+        Ok(value) => Ok(value as f64),
+        Err(error) if *error.kind() == std::num::IntErrorKind::PosOverflow => {
+            Err(format!("Number '{}' is too large.", text))
+        }
+        Err(_) => Err(format!("Malformed number '{}'.", text)),
+    }
+}
+
 fn is_alpha_numeric(c: char) -> bool {
     is_alpha(c) || is_digit(c)
 }
 
 fn is_alpha(c: char) -> bool {
-    c.is_ascii_alphabetic()
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+// Identifiers as in Rust and Python, UAX #31: XID_Start or `_` first, then
+// XID_Continue, which adds digits, combining marks and connectors like `_`.
+// Names aren't normalized: `ä` and `a` followed by U+0308 are both accepted,
+// but as different names.
+fn is_unicode_alpha(c: char) -> bool {
+    unicode_ident::is_xid_start(c) || c == '_'
+}
+
+fn is_unicode_alpha_numeric(c: char) -> bool {
+    unicode_ident::is_xid_continue(c)
 }
 
 fn is_digit(c: char) -> bool {
     c.is_ascii_digit()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(source: &str, unicode_identifiers: bool) -> (Vec<Token>, Vec<ScanError>) {
+        Scanner::new(source.to_string(), 0)
+            .with_unicode_identifiers(unicode_identifiers)
+            .scan_tokens()
+    }
+
+    fn messages(errors: &[ScanError]) -> Vec<&str> {
+        errors.iter().map(|error| error.message.as_str()).collect()
+    }
+
+    #[test]
+    fn unicode_identifiers() {
+        let names = [
+            "päivä",
+            // The same word in NFD, with U+0308 COMBINING DIAERESIS.
+            "pa\u{308}iva\u{308}",
+            "_private",
+            "λ",
+            "变量",
+            "x\u{203F}y",
+            "a1",
+        ];
+        for name in names {
+            let (tokens, errors) = scan(&format!("var {} = 1;", name), true);
+            assert!(errors.is_empty(), "{}: {:?}", name, messages(&errors));
+            assert_eq!(tokens[1].token_type, TokenType::Identifier);
+            assert_eq!(tokens[1].lexeme, name);
+        }
+    }
+
+    #[test]
+    fn characters_outside_identifiers() {
+        let cases = [
+            // Superscripts are numbers, but not XID_Continue.
+            ("x²", "Unexpected character '²'."),
+            // A mark can't start a name.
+            ("\u{308}a", "Unexpected character '\u{308}'."),
+            ("€", "Unexpected character '€'."),
+        ];
+        for (source, message) in cases {
+            let (_, errors) = scan(source, true);
+            assert_eq!(messages(&errors), [message], "{}", source);
+        }
+    }
+
+    #[test]
+    fn identifiers_are_ascii_by_default() {
+        let (_, errors) = scan("var päivä;", false);
+        assert_eq!(
+            messages(&errors),
+            ["Unexpected character 'ä'.", "Unexpected character 'ä'."]
+        );
+    }
+
+    #[test]
+    fn non_ascii_strings() {
+        for unicode_identifiers in [false, true] {
+            let (tokens, errors) = scan("print \"päivä 🌍\"; x", unicode_identifiers);
+            assert!(errors.is_empty(), "{:?}", messages(&errors));
+            assert_eq!(
+                tokens[1].token_type,
+                TokenType::String {
+                    literal: "päivä 🌍".to_string()
+                }
+            );
+            assert_eq!(tokens[1].lexeme, "\"päivä 🌍\"");
+            // Columns count chars, not bytes.
+            assert_eq!(tokens[2].span.column, 16);
+            assert_eq!(tokens[3].span.column, 18);
+        }
+    }
+
+    fn with_trivia(source: &str) -> (Vec<TriviaToken>, Vec<ScanError>) {
+        Scanner::new(source.to_string(), 0).scan_tokens_with_trivia()
+    }
+
+    fn rebuild(tokens: &[TriviaToken]) -> String {
+        let mut text = String::new();
+        for token in tokens {
+            for trivia in &token.leading {
+                text.push_str(&trivia.text);
+            }
+            text.push_str(&token.token.lexeme);
+        }
+        text
+    }
+
+    #[test]
+    fn trivia_reproduces_the_source() {
+        let sources = [
+            "",
+            "   \n\t",
+            "print 1;",
+            "// comment only",
+            "var a = 1; // trailing\r\n/* block */ print a;\n",
+            "fun f(x) {\n\treturn x * 2;\n}\n\n\n",
+            "print \"a ${ 1 + 2 } b ${\"c\"}\"; /* end */",
+            "print \"päivä\" /* 🌍 */ ;",
+            "/* /* nested */ still comment */ print 1;",
+        ];
+        for source in sources {
+            let (tokens, errors) = with_trivia(source);
+            assert!(errors.is_empty(), "{:?}: {:?}", source, messages(&errors));
+            assert_eq!(rebuild(&tokens), source);
+        }
+    }
+
+    #[test]
+    fn nested_block_comments() {
+        let (tokens, errors) = with_trivia("/* a /* b */ c */ x");
+        assert!(errors.is_empty(), "{:?}", messages(&errors));
+        assert_eq!(tokens[0].token.lexeme, "x");
+        assert_eq!(tokens[0].leading.len(), 2);
+        assert_eq!(tokens[0].leading[0].kind, TriviaKind::BlockComment);
+        assert_eq!(tokens[0].leading[0].text, "/* a /* b */ c */");
+        assert_eq!(tokens[0].leading[1].kind, TriviaKind::Whitespace);
+    }
+
+    #[test]
+    fn unterminated_block_comment() {
+        let source = "print 1;\n/* a /* b */ never closed";
+        let (tokens, errors) = with_trivia(source);
+        assert_eq!(messages(&errors), ["Unterminated block comment."]);
+        assert_eq!(errors[0].span.line, 2);
+        assert_eq!(errors[0].span.column, 1);
+        // The rest of the file is kept, as the `Eof` token's trivia.
+        assert_eq!(rebuild(&tokens), source);
+        let eof = tokens.last().unwrap();
+        assert_eq!(eof.token.token_type, TokenType::Eof);
+        assert_eq!(
+            eof.leading.last().unwrap().text,
+            "/* a /* b */ never closed"
+        );
+    }
+}
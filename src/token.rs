@@ -6,6 +6,8 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -13,6 +15,8 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Percent,
+    Colon,
 
     // One or two character tokens.
     Bang,
@@ -23,10 +27,23 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    Arrow,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    PlusPlus,
+    MinusMinus,
+    StarStar,
 
     // Literals.
     Identifier,
     String { literal: String },
+    // The part of an interpolated string up to a `${`. The embedded
+    // expression's tokens follow, then the rest of the string as another
+    // `Interpolation` or a closing `String`.
+    Interpolation { literal: String },
     Number { literal: f64 },
 
     // Keywords.
@@ -46,18 +63,80 @@ pub enum TokenType {
     True,
     Var,
     While,
+    Try,
+    Catch,
+    Finally,
+    Throw,
+    Break,
+    Continue,
 
     Eof,
 }
+// Location of a piece of source. `start` and `end` are byte offsets into the
+// file, `line` and `column` are 1-based and point at `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub file_id: usize,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    // Smallest span covering both `self` and `other`.
+    pub fn to(&self, other: Span) -> Span {
+        if other.start < self.start {
+            return other.to(*self);
+        }
+        Span {
+            end: self.end.max(other.end),
+            ..*self
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
-    pub line: usize,
+    pub span: Span,
 }
 
-impl<'a> fmt::Display for Token {
+impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "({:?} {} )", self.token_type, self.lexeme)
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriviaKind {
+    // Spaces, tabs and carriage returns.
+    Whitespace,
+    Newline,
+    LineComment,
+    BlockComment,
+}
+
+// Source text the parser never sees. Only kept when scanning with trivia.
+#[derive(Debug, Clone)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+// A token with the trivia in front of it. Writing out every token's
+// `leading` text followed by its lexeme reproduces the source exactly; trivia
+// at the end of the file belongs to the `Eof` token.
+#[derive(Debug, Clone)]
+pub struct TriviaToken {
+    pub leading: Vec<Trivia>,
+    pub token: Token,
+}
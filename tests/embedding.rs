@@ -0,0 +1,225 @@
+// The library API a host program uses: `Lox` with its globals, calls into
+// Lox functions, natives written in Rust, and rendering errors.
+
+use lox::expr::Stmt;
+use lox::parser::Parser;
+use lox::scanner::Scanner;
+use lox::token::{Span, Token, TokenType};
+use lox::{Arity, Interpreter, Lox, LoxError, Object, Renderer, RuntimeError};
+
+#[test]
+fn eval_returns_a_trailing_expression() {
+    let mut lox = Lox::new();
+    assert_eq!(lox.eval("1 + 2;").unwrap(), Object::Number(3.0));
+    assert_eq!(lox.eval("var x = 1;").unwrap(), Object::Nil);
+}
+
+#[test]
+fn globals_persist_between_evals() {
+    let mut lox = Lox::new();
+    lox.eval("var greeting = \"hi\";").unwrap();
+    assert_eq!(
+        lox.eval("greeting + \"!\";").unwrap(),
+        Object::String("hi!".to_string())
+    );
+    assert_eq!(
+        lox.get_global("greeting"),
+        Some(Object::String("hi".to_string()))
+    );
+    assert_eq!(lox.get_global("missing"), None);
+}
+
+#[test]
+fn set_global_is_visible_to_scripts() {
+    let mut lox = Lox::new();
+    lox.set_global("base", Object::Number(40.0));
+    assert_eq!(lox.eval("base + 2;").unwrap(), Object::Number(42.0));
+}
+
+#[test]
+fn call_runs_a_lox_function() {
+    let mut lox = Lox::new();
+    lox.eval("fun add(a, b) { return a + b; }").unwrap();
+    let sum = lox
+        .call("add", vec![Object::Number(2.0), Object::Number(3.0)])
+        .unwrap();
+    assert_eq!(sum, Object::Number(5.0));
+}
+
+#[test]
+fn define_native_is_callable_from_scripts() {
+    let mut lox = Lox::new();
+    lox.define_native("double", Arity::Fixed(1), |_, args| match &args[0] {
+        Object::Number(number) => Ok(Object::Number(number * 2.0)),
+        _ => Err(RuntimeError::native("Expected a number.".to_string())),
+    });
+    assert_eq!(lox.eval("double(21);").unwrap(), Object::Number(42.0));
+    assert_eq!(
+        lox.call("double", vec![Object::Number(1.0)]).unwrap(),
+        Object::Number(2.0)
+    );
+}
+
+// Errors raised on the host's side of `call` have no place in any source,
+// even after a script has been evaluated.
+#[test]
+fn host_errors_render_without_a_location() {
+    let mut lox = Lox::new();
+    lox.eval("fun one(a) { return a; }").unwrap();
+    lox.define_native("fail", Arity::Fixed(0), |_, _| {
+        Err(RuntimeError::native("Nope.".to_string()))
+    });
+
+    let cases = [
+        (lox.call("nope", vec![]), "Undefined variable 'nope'."),
+        (lox.call("one", vec![]), "Expected 1 arguments but got 0."),
+        (lox.call("fail", vec![]), "fail(): Nope."),
+    ];
+    for (result, message) in cases {
+        let error = result.unwrap_err();
+        assert!(matches!(error, LoxError::Runtime(_)));
+        let rendered = lox.render(&error, &Renderer::plain());
+        assert_eq!(rendered, format!("error[E0004]: {}\n", message));
+    }
+}
+
+#[test]
+fn script_errors_render_with_their_source() {
+    let mut lox = Lox::new();
+    let error = lox.eval("var a = 1;\nprint a + nil;").unwrap_err();
+    let rendered = lox.render(&error, &Renderer::plain());
+    assert!(rendered.contains(" --> <eval>:2:9"), "{}", rendered);
+    assert!(rendered.contains("2 | print a + nil;"), "{}", rendered);
+}
+
+#[test]
+fn parse_errors_are_reported_and_leave_globals_alone() {
+    let mut lox = Lox::new();
+    lox.eval("var kept = 1;").unwrap();
+    let error = lox.eval("var broken = ;").unwrap_err();
+    assert!(matches!(error, LoxError::Parse(_)));
+    assert_eq!(lox.get_global("kept"), Some(Object::Number(1.0)));
+    assert_eq!(lox.get_global("broken"), None);
+}
+
+// The scanner, parser and interpreter can be driven one by one. Skipping the
+// resolver lets a misplaced `return` reach the interpreter, which reports it
+// instead of panicking. So does a `break` in a hand-built tree.
+#[test]
+fn unresolved_jumps_are_runtime_errors() {
+    let parse = |source: &str| {
+        let (tokens, errors) = Scanner::new(source.to_string(), 0).scan_tokens();
+        assert!(errors.is_empty());
+        Parser::new(tokens).parse().ok().unwrap()
+    };
+    let keyword = |token_type, lexeme: &str| Token {
+        token_type,
+        lexeme: lexeme.to_string(),
+        span: Span::default(),
+    };
+    let cases = [
+        (parse("return 1;"), "Can't return from top-level code."),
+        (
+            parse("{ print 1; return; }"),
+            "Can't return from top-level code.",
+        ),
+        (
+            vec![Stmt::Break {
+                keyword: keyword(TokenType::Break, "break"),
+            }],
+            "Can't use 'break' outside of a loop.",
+        ),
+        (
+            vec![Stmt::Continue {
+                keyword: keyword(TokenType::Continue, "continue"),
+            }],
+            "Can't use 'continue' outside of a loop.",
+        ),
+    ];
+    for (statements, message) in cases {
+        let mut interpreter = Interpreter::new();
+        let error = interpreter.interpret_stmts(statements).unwrap_err();
+        assert_eq!(error.message, message);
+    }
+}
+
+// `exit` ends the script, not the host.
+#[test]
+fn exit_returns_to_the_host() {
+    let mut lox = Lox::new();
+    let error = lox
+        .eval("var kept = 1; try { exit(3); } catch (e) { kept = 2; } kept = 4;")
+        .unwrap_err();
+    assert!(matches!(error, LoxError::Exit(3)));
+    assert_eq!(lox.get_global("kept"), Some(Object::Number(1.0)));
+
+    lox.eval("fun quit() { exit(); }").unwrap();
+    assert!(matches!(lox.call("quit", vec![]), Err(LoxError::Exit(0))));
+    assert_eq!(lox.eval("kept + 1;").unwrap(), Object::Number(2.0));
+}
+
+#[test]
+fn runtime_errors_in_functions_render_a_traceback() {
+    let mut lox = Lox::new();
+    let source = "\
+fun f(n) {
+  if (n == 0) return nil + 1;
+  return f(n - 1);
+}
+fun g() { f(6); }
+g();";
+    let error = lox.eval_named("deep.lox", source).unwrap_err();
+    let LoxError::Runtime(runtime) = &error else {
+        panic!("Expected a runtime error, got {:?}.", error);
+    };
+    let traceback = Renderer::plain().render_backtrace(runtime, lox.sources());
+    assert_eq!(
+        traceback,
+        "\
+Traceback (most recent call last):
+  File \"deep.lox\", line 6, column 3, in <script>
+    g();
+  File \"deep.lox\", line 5, column 14, in g
+    fun g() { f(6); }
+  File \"deep.lox\", line 3, column 17, in f
+    return f(n - 1);
+  File \"deep.lox\", line 3, column 17, in f
+    return f(n - 1);
+  File \"deep.lox\", line 3, column 17, in f
+    return f(n - 1);
+  [Previous line repeated 3 more times]
+  File \"deep.lox\", line 2, column 26, in f
+    if (n == 0) return nil + 1;
+"
+    );
+    let rendered = lox.render(&error, &Renderer::plain());
+    assert!(rendered.starts_with(&traceback), "{}", rendered);
+
+    // Errors in top-level code have no traceback.
+    let error = lox.eval("nil + 1;").unwrap_err();
+    let LoxError::Runtime(runtime) = &error else {
+        panic!("Expected a runtime error, got {:?}.", error);
+    };
+    assert_eq!(
+        Renderer::plain().render_backtrace(runtime, lox.sources()),
+        ""
+    );
+}
+
+// Runaway recursion is an error for the host to handle, on the test
+// harness's small thread stack too.
+#[test]
+fn runaway_recursion_returns_an_error() {
+    let mut lox = Lox::new();
+    let error = lox.eval("fun f() { f(); } f();").unwrap_err();
+    let LoxError::Runtime(runtime) = &error else {
+        panic!("Expected a runtime error, got {:?}.", error);
+    };
+    assert_eq!(runtime.message, "Stack overflow.");
+    assert!(matches!(lox.call("f", vec![]), Err(LoxError::Runtime(_))));
+
+    assert_eq!(lox.eval("1 + 2;").unwrap(), Object::Number(3.0));
+    lox.eval("fun count(n) { if (n == 0) return 0; return count(n - 1) + 1; }")
+        .unwrap();
+    assert_eq!(lox.eval("count(500);").unwrap(), Object::Number(500.0));
+}
@@ -0,0 +1,295 @@
+// Runs every `.lox` file under `tests/` through the `lox` binary and checks
+// it against annotations in its comments, like the Crafting Interpreters
+// test suite does:
+//
+//     print 1 + 2; // expect: 3
+//     nil + 1;     // expect runtime error: Operands must be two numbers ...
+//     print (;     // [line 3] Error at ';': Expect expression.
+//
+// `expect:` lines are the script's standard output, in order. A compile
+// error annotation without `[line N]` is for the line it's on.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// What a script says should happen when it runs.
+#[derive(Default)]
+struct Expectations {
+    output: Vec<String>,
+    runtime_error: Option<Error>,
+    compile_errors: Vec<Error>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Error {
+    line: usize,
+    message: String,
+}
+
+impl Expectations {
+    fn parse(source: &str) -> Expectations {
+        let mut expectations = Expectations::default();
+        for (index, text) in source.lines().enumerate() {
+            let line = index + 1;
+            if let Some(output) = after(text, "// expect: ") {
+                expectations.output.push(output.to_string());
+            } else if let Some(message) = after(text, "// expect runtime error: ") {
+                expectations.runtime_error = Some(Error {
+                    line,
+                    message: message.to_string(),
+                });
+            } else if let Some(rest) = after(text, "// [line ") {
+                let (number, message) = rest
+                    .split_once("] ")
+                    .unwrap_or_else(|| panic!("Malformed annotation on line {}.", line));
+                expectations.compile_errors.push(Error {
+                    line: number.parse().expect("line number in annotation"),
+                    message: message.to_string(),
+                });
+            } else if let Some(message) = after(text, "// Error") {
+                expectations.compile_errors.push(Error {
+                    line,
+                    message: format!("Error{}", message),
+                });
+            }
+        }
+        expectations
+    }
+
+    // Exit codes of the `lox` binary, which follow the reference
+    // implementation.
+    fn exit_code(&self) -> i32 {
+        if !self.compile_errors.is_empty() {
+            65
+        } else if self.runtime_error.is_some() {
+            70
+        } else {
+            0
+        }
+    }
+}
+
+fn after<'a>(text: &'a str, marker: &str) -> Option<&'a str> {
+    text.find(marker).map(|start| &text[start + marker.len()..])
+}
+
+// One diagnostic from the binary's standard error, e.g.
+//
+//     error[E0002]: Expect expression.
+//      --> tests/parsing/missing_expression.lox:1:7
+//       |
+//     1 | print ;
+//       |       ^ at ';'
+struct Reported {
+    code: String,
+    line: usize,
+    message: String,
+    label: Option<String>,
+}
+
+impl Reported {
+    fn parse_all(stderr: &str) -> Vec<Reported> {
+        let mut reported: Vec<Reported> = Vec::new();
+        for text in stderr.lines() {
+            if let Some(rest) = text.strip_prefix("error[") {
+                if let Some((code, message)) = rest.split_once("]: ") {
+                    reported.push(Reported {
+                        code: code.to_string(),
+                        line: 0,
+                        message: message.to_string(),
+                        label: None,
+                    });
+                }
+                continue;
+            }
+            let Some(current) = reported.last_mut() else {
+                continue;
+            };
+            if let Some(location) = text.trim_start().strip_prefix("--> ") {
+                let mut parts = location.rsplitn(3, ':');
+                let _column = parts.next();
+                current.line = parts.next().and_then(|line| line.parse().ok()).unwrap_or(0);
+            } else if let Some((_, label)) = text.split_once("| ") {
+                let label = label.trim_start();
+                if label.starts_with('^') && current.label.is_none() {
+                    let label = label.trim_start_matches('^').trim();
+                    if !label.is_empty() {
+                        current.label = Some(label.to_string());
+                    }
+                }
+            }
+        }
+        reported
+    }
+
+    fn is_runtime(&self) -> bool {
+        self.code == "E0004"
+    }
+
+    // In the reference format: "Error at 'x': message".
+    fn as_compile_error(&self) -> Error {
+        let message = match self.label.as_deref() {
+            Some("at end of input") => format!("Error at end: {}", self.message),
+            Some(label) => format!("Error {}: {}", label, self.message),
+            None => format!("Error: {}", self.message),
+        };
+        Error {
+            line: self.line,
+            message,
+        }
+    }
+}
+
+// Describes everything that went differently than `path` expects, or
+// nothing if the script passed.
+fn check(path: &Path) -> Vec<String> {
+    let source = fs::read_to_string(path).expect("readable test script");
+    let expected = Expectations::parse(&source);
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg(path)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("lox binary to run");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let reported = Reported::parse_all(&stderr);
+    let mut problems = Vec::new();
+
+    let actual_output: Vec<String> = stdout.lines().map(str::to_string).collect();
+    if actual_output != expected.output {
+        problems.push(format!(
+            "Standard output differs (- expected, + actual):\n{}",
+            diff(&expected.output, &actual_output)
+        ));
+    }
+
+    let compile_errors: Vec<Error> = reported
+        .iter()
+        .filter(|reported| !reported.is_runtime())
+        .map(Reported::as_compile_error)
+        .collect();
+    if compile_errors != expected.compile_errors {
+        let format = |errors: &[Error]| -> Vec<String> {
+            errors
+                .iter()
+                .map(|error| format!("[line {}] {}", error.line, error.message))
+                .collect()
+        };
+        problems.push(format!(
+            "Compile errors differ (- expected, + actual):\n{}",
+            diff(&format(&expected.compile_errors), &format(&compile_errors))
+        ));
+    }
+
+    let runtime_error = reported
+        .iter()
+        .find(|reported| reported.is_runtime())
+        .map(|reported| Error {
+            line: reported.line,
+            message: reported.message.clone(),
+        });
+    if runtime_error != expected.runtime_error {
+        let format = |error: &Option<Error>| match error {
+            Some(error) => format!("'{}' on line {}", error.message, error.line),
+            None => "none".to_string(),
+        };
+        problems.push(format!(
+            "Expected runtime error {}, got {}.",
+            format(&expected.runtime_error),
+            format(&runtime_error)
+        ));
+    }
+
+    let exit_code = output.status.code().unwrap_or(-1);
+    if exit_code != expected.exit_code() {
+        problems.push(format!(
+            "Expected exit code {}, got {}.",
+            expected.exit_code(),
+            exit_code
+        ));
+    }
+
+    // Anything unexplained on stderr, e.g. a panic, is shown in full.
+    if !problems.is_empty() && !stderr.is_empty() {
+        problems.push(format!("Standard error was:\n{}", indent(&stderr)));
+    }
+    problems
+}
+
+// A line diff over the longest common subsequence. Test output is short, so
+// the quadratic table is fine.
+fn diff(expected: &[String], actual: &[String]) -> String {
+    let (n, m) = (expected.len(), actual.len());
+    let mut common = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && expected[i] == actual[j] {
+            lines.push(format!("  {}", expected[i]));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(format!("- {}", expected[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+ {}", actual[j]));
+            j += 1;
+        }
+    }
+    indent(&lines.join("\n"))
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn collect_scripts(directory: &Path, scripts: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(directory).expect("readable tests directory") {
+        let path = entry.expect("directory entry").path();
+        if path.is_dir() {
+            collect_scripts(&path, scripts);
+        } else if path.extension().is_some_and(|extension| extension == "lox") {
+            scripts.push(path);
+        }
+    }
+}
+
+#[test]
+fn lox_scripts() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut scripts = Vec::new();
+    collect_scripts(&root.join("tests"), &mut scripts);
+    scripts.sort();
+    assert!(!scripts.is_empty(), "No .lox scripts found under tests/.");
+
+    let mut failures = Vec::new();
+    for script in &scripts {
+        let problems = check(script);
+        if !problems.is_empty() {
+            let name = script.strip_prefix(root).unwrap_or(script);
+            failures.push(format!("{}\n{}", name.display(), problems.join("\n")));
+        }
+    }
+    if !failures.is_empty() {
+        panic!(
+            "{} of {} scripts failed:\n\n{}\n",
+            failures.len(),
+            scripts.len(),
+            failures.join("\n\n")
+        );
+    }
+}